@@ -0,0 +1,22 @@
+struct Point {
+    x: felt,
+    y: felt,
+}
+
+// Sums felts taken from every kind of argument, counting its calls in an implicit argument.
+func sum_args{calls}(
+    a: felt, point: Point, points: Point*, pair: (felt, felt*), n_values: felt, values: felt*
+) -> (sum: felt) {
+    let calls = calls + 1;
+    let struct_sum = point.x + point.y + points[0].x + points[1].y;
+    let pointer_sum = [pair[1]] + values[n_values - 1];
+    return (sum=a + struct_sum + pair[0] + pointer_sum);
+}
+
+func swap(point: Point, values: felt*) -> (swapped: Point, pair: (felt, felt*)) {
+    return (swapped=Point(x=point.y, y=point.x), pair=(point.x, values));
+}
+
+func main() {
+    return ();
+}
@@ -0,0 +1,10 @@
+func main() {
+    let x = 3;
+
+    %{
+        y = ids.x + 1
+        raise ValueError(f"y is {y}")
+    %}
+
+    return ();
+}
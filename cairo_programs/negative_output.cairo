@@ -0,0 +1,9 @@
+%builtins output
+
+from starkware.cairo.common.serialize import serialize_word
+
+func main{output_ptr: felt*}() {
+    serialize_word(7);
+    serialize_word(-3);
+    return ();
+}
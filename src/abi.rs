@@ -0,0 +1,435 @@
+use crate::{
+    relocatable::{PyMaybeRelocatable, PyRelocatable},
+    vm_core::CAIRO_PRIME,
+};
+use cairo_vm::{
+    serde::deserialize_program::Member,
+    types::relocatable::{MaybeRelocatable, Relocatable},
+    vm::vm_core::VirtualMachine,
+};
+use num_bigint::{BigInt, Sign};
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyDict, PyString, PyTuple},
+};
+use std::collections::HashMap;
+
+const FUNCTION_ARGS_STRUCTS: [&str; 2] = ["ImplicitArgs", "Args"];
+
+type StructTypes = HashMap<String, HashMap<String, Member>>;
+
+/// Encodes plain Python values as the arguments of a function, following the
+/// members of its `ImplicitArgs` and `Args` structs.
+///
+/// Felts are ints, structs and tuples are dicts keyed by member name or
+/// sequences in member order, and pointers are either a relocatable, a list
+/// of pointee values or a dict holding a single pointee struct. Ints are
+/// reduced modulo the prime only when `apply_modulo` is set, otherwise ints
+/// outside of `[0, PRIME)` are rejected.
+pub(crate) struct ArgsEncoder<'a> {
+    struct_types: &'a StructTypes,
+    vm: &'a mut VirtualMachine,
+    apply_modulo: bool,
+}
+
+impl<'a> ArgsEncoder<'a> {
+    pub fn new(
+        struct_types: &'a StructTypes,
+        vm: &'a mut VirtualMachine,
+        apply_modulo: bool,
+    ) -> Self {
+        ArgsEncoder {
+            struct_types,
+            vm,
+            apply_modulo,
+        }
+    }
+
+    pub fn encode_function_args(
+        &mut self,
+        function: &str,
+        args: &PyAny,
+    ) -> PyResult<Vec<MaybeRelocatable>> {
+        let struct_types = self.struct_types;
+        let members = FUNCTION_ARGS_STRUCTS
+            .iter()
+            .filter_map(|name| struct_types.get(&format!("{function}.{name}")))
+            .flat_map(sorted_members)
+            .collect::<Vec<_>>();
+
+        let mut stack = Vec::new();
+        self.encode_members(
+            &members,
+            args,
+            &format!("{function} arguments"),
+            "",
+            &mut stack,
+        )?;
+        Ok(stack)
+    }
+
+    fn encode(
+        &mut self,
+        value: &PyAny,
+        cairo_type: &str,
+        path: &str,
+        stack: &mut Vec<MaybeRelocatable>,
+    ) -> PyResult<()> {
+        let struct_types = self.struct_types;
+        if let Some(pointee) = cairo_type.strip_suffix('*') {
+            let pointer = self.encode_pointer(value, pointee, path)?;
+            stack.push(pointer);
+        } else if cairo_type == "felt" || cairo_type == "codeoffset" {
+            stack.push(encode_felt(value, cairo_type, path, self.apply_modulo)?);
+        } else if cairo_type.starts_with('(') {
+            let members = tuple_members(cairo_type);
+            self.encode_members(&members, value, cairo_type, &format!("{path}."), stack)?;
+        } else if let Some(struct_type) = struct_types.get(cairo_type) {
+            let members = sorted_members(struct_type);
+            self.encode_members(&members, value, cairo_type, &format!("{path}."), stack)?;
+        } else {
+            return Err(PyValueError::new_err(format!(
+                "Unknown type {cairo_type} of argument '{path}'"
+            )));
+        }
+        Ok(())
+    }
+
+    fn encode_members(
+        &mut self,
+        members: &[(String, &str)],
+        value: &PyAny,
+        owner: &str,
+        prefix: &str,
+        stack: &mut Vec<MaybeRelocatable>,
+    ) -> PyResult<()> {
+        let missing = |name: &str, cairo_type: &str| {
+            PyValueError::new_err(format!(
+                "Missing argument '{prefix}{name}' of type {cairo_type}"
+            ))
+        };
+
+        if let Ok(dict) = value.downcast::<PyDict>() {
+            for key in dict.keys() {
+                let key = key.str()?.to_str()?;
+                if !members.iter().any(|(name, _)| name == key) {
+                    return Err(PyValueError::new_err(format!(
+                        "Unexpected argument '{prefix}{key}', {owner} has members: {}",
+                        format_members(members)
+                    )));
+                }
+            }
+            for (name, cairo_type) in members {
+                let value = dict
+                    .get_item(name)
+                    .ok_or_else(|| missing(name, cairo_type))?;
+                self.encode(value, cairo_type, &format!("{prefix}{name}"), stack)?;
+            }
+        } else {
+            let values = sequence_items(value).map_err(|_| {
+                PyValueError::new_err(format!(
+                    "Expected a dict or a sequence holding the members of {owner}"
+                ))
+            })?;
+            if let Some((name, cairo_type)) = members.get(values.len()) {
+                return Err(missing(name, cairo_type));
+            }
+            if values.len() > members.len() {
+                return Err(PyValueError::new_err(format!(
+                    "Unexpected argument at position {}, {owner} has members: {}",
+                    members.len(),
+                    format_members(members)
+                )));
+            }
+            for ((name, cairo_type), value) in members.iter().zip(values) {
+                self.encode(value, cairo_type, &format!("{prefix}{name}"), stack)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_pointer(
+        &mut self,
+        value: &PyAny,
+        pointee: &str,
+        path: &str,
+    ) -> PyResult<MaybeRelocatable> {
+        // Ints aren't taken as pointers, as they'd point nowhere
+        if let Ok(pointer) = value.extract::<PyRelocatable>() {
+            return Ok(Relocatable::from(&pointer).into());
+        }
+
+        let mut data = Vec::new();
+        if value.downcast::<PyDict>().is_ok() {
+            self.encode(value, pointee, path, &mut data)?;
+        } else {
+            let elements = sequence_items(value).map_err(|_| {
+                PyValueError::new_err(format!(
+                    "Expected a pointer, a list or a dict for argument '{path}' of type {pointee}*"
+                ))
+            })?;
+            for (index, element) in elements.into_iter().enumerate() {
+                self.encode(element, pointee, &format!("{path}[{index}]"), &mut data)?;
+            }
+        }
+
+        let base = self.vm.add_memory_segment();
+        self.vm
+            .load_data(base, &data)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(base.into())
+    }
+}
+
+/// Collects the items of a list, tuple or any other iterable, except for
+/// strings, which would otherwise be encoded one character at a time.
+fn sequence_items(value: &PyAny) -> PyResult<Vec<&PyAny>> {
+    if value.downcast::<PyString>().is_ok() {
+        return Err(PyTypeError::new_err("Strings are not sequences of values"));
+    }
+    value.iter()?.collect()
+}
+
+fn encode_felt(
+    value: &PyAny,
+    cairo_type: &str,
+    path: &str,
+    apply_modulo: bool,
+) -> PyResult<MaybeRelocatable> {
+    let value = value.extract::<BigInt>().map_err(|_| {
+        PyValueError::new_err(format!(
+            "Expected an int for argument '{path}' of type {cairo_type}"
+        ))
+    })?;
+    let prime = BigInt::from(CAIRO_PRIME.clone());
+    let value = if apply_modulo {
+        ((value % &prime) + &prime) % prime
+    } else if value.sign() == Sign::Minus || value >= prime {
+        return Err(PyValueError::new_err(format!(
+            "Argument '{path}' is out of range: {value}"
+        )));
+    } else {
+        value
+    };
+    // The value is non-negative after the reduction
+    Ok(MaybeRelocatable::Int(
+        value.to_biguint().unwrap_or_default().into(),
+    ))
+}
+
+/// Returns the amount of memory cells taken by a value of the given type.
+pub(crate) fn type_size(struct_types: &StructTypes, cairo_type: &str) -> PyResult<usize> {
+    if cairo_type.ends_with('*') || cairo_type == "felt" || cairo_type == "codeoffset" {
+        Ok(1)
+    } else if cairo_type.starts_with('(') {
+        tuple_members(cairo_type)
+            .into_iter()
+            .map(|(_, member_type)| type_size(struct_types, member_type))
+            .sum()
+    } else if let Some(struct_type) = struct_types.get(cairo_type) {
+        let mut size = 0;
+        for member in struct_type.values() {
+            size = size.max(member.offset + type_size(struct_types, &member.cairo_type)?);
+        }
+        Ok(size)
+    } else {
+        Err(PyValueError::new_err(format!("Unknown type {cairo_type}")))
+    }
+}
+
+/// Decodes the values of the given type into Python objects. Structs and named
+/// tuples become dicts keyed by member name, unnamed tuples become tuples and
+/// felts and pointers become either ints or `RelocatableValue`s.
+pub(crate) fn decode_value(
+    py: Python,
+    struct_types: &StructTypes,
+    values: &[MaybeRelocatable],
+    cairo_type: &str,
+) -> PyResult<PyObject> {
+    if cairo_type.ends_with('*') || cairo_type == "felt" || cairo_type == "codeoffset" {
+        let value = values
+            .first()
+            .ok_or_else(|| PyValueError::new_err(format!("Missing value of type {cairo_type}")))?;
+        return Ok(PyMaybeRelocatable::from(value).to_object(py));
+    }
+
+    let members = if cairo_type.starts_with('(') {
+        let mut offset = 0;
+        let mut members = Vec::new();
+        for (name, member_type) in tuple_members(cairo_type) {
+            members.push((name, member_type, offset));
+            offset += type_size(struct_types, member_type)?;
+        }
+        members
+    } else if let Some(struct_type) = struct_types.get(cairo_type) {
+        let mut members = struct_type
+            .iter()
+            .map(|(name, member)| (name.clone(), member.cairo_type.as_str(), member.offset))
+            .collect::<Vec<_>>();
+        members.sort_by_key(|(_, _, offset)| *offset);
+        members
+    } else {
+        return Err(PyValueError::new_err(format!("Unknown type {cairo_type}")));
+    };
+
+    let mut decoded = Vec::new();
+    for (name, member_type, offset) in members {
+        let member_values = values.get(offset..).unwrap_or_default();
+        decoded.push((
+            name,
+            decode_value(py, struct_types, member_values, member_type)?,
+        ));
+    }
+
+    // Unnamed tuple members are named after their index, which can't be an identifier
+    let is_unnamed_tuple = cairo_type.starts_with('(')
+        && decoded
+            .iter()
+            .all(|(name, _)| name.chars().all(|char| char.is_ascii_digit()));
+    if is_unnamed_tuple {
+        let values = decoded
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>();
+        return Ok(PyTuple::new(py, values).to_object(py));
+    }
+    let dict = PyDict::new(py);
+    for (name, value) in decoded {
+        dict.set_item(name, value)?;
+    }
+    Ok(dict.to_object(py))
+}
+
+/// Returns the name and type of a struct's members in memory order.
+fn sorted_members(members: &HashMap<String, Member>) -> Vec<(String, &str)> {
+    let mut members = members.iter().collect::<Vec<_>>();
+    members.sort_by_key(|(_, member)| member.offset);
+    members
+        .into_iter()
+        .map(|(name, member)| (name.clone(), member.cairo_type.as_str()))
+        .collect()
+}
+
+/// Splits a tuple type such as `(felt, felt*)` or `(x: felt, y: (felt, felt))`
+/// into its members, naming the unnamed ones after their index.
+fn tuple_members(cairo_type: &str) -> Vec<(String, &str)> {
+    let inner = &cairo_type[1..cairo_type.len() - 1];
+    let mut members = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (index, char) in inner.char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                members.push(&inner[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if !inner[start..].trim().is_empty() {
+        members.push(&inner[start..]);
+    }
+
+    members
+        .into_iter()
+        .enumerate()
+        .map(|(index, member)| {
+            let member = member.trim();
+            match member.split_once(':') {
+                Some((name, cairo_type)) if !name.contains('(') => {
+                    (name.trim().to_string(), cairo_type.trim())
+                }
+                _ => (index.to_string(), member),
+            }
+        })
+        .collect()
+}
+
+fn format_members(members: &[(String, &str)]) -> String {
+    members
+        .iter()
+        .map(|(name, cairo_type)| format!("{name}: {cairo_type}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_tuple_members() {
+        assert_eq!(
+            tuple_members("(felt, felt*)"),
+            vec![("0".to_string(), "felt"), ("1".to_string(), "felt*")]
+        );
+        assert_eq!(
+            tuple_members("(x: felt, y: (a: felt, felt))"),
+            vec![
+                ("x".to_string(), "felt"),
+                ("y".to_string(), "(a: felt, felt)")
+            ]
+        );
+        assert!(tuple_members("()").is_empty());
+    }
+
+    #[test]
+    fn negative_felts_are_reduced() {
+        Python::with_gil(|py| {
+            let value = encode_felt((-1).to_object(py).as_ref(py), "felt", "a", true).unwrap();
+            let expected: BigInt = BigInt::from(CAIRO_PRIME.clone()) - 1;
+            assert_eq!(
+                value,
+                MaybeRelocatable::Int(expected.to_biguint().unwrap().into())
+            );
+            assert!(encode_felt("a".to_object(py).as_ref(py), "felt", "a", true).is_err());
+        });
+    }
+
+    #[test]
+    fn out_of_range_felts_are_rejected_without_modulo() {
+        Python::with_gil(|py| {
+            let prime = BigInt::from(CAIRO_PRIME.clone());
+            for value in [BigInt::from(-1), prime.clone()] {
+                let value = value.to_object(py);
+                assert!(encode_felt(value.as_ref(py), "felt", "a", false).is_err());
+            }
+            let value = (prime - BigInt::from(1)).to_object(py);
+            assert!(encode_felt(value.as_ref(py), "felt", "a", false).is_ok());
+        });
+    }
+
+    #[test]
+    fn ints_are_not_pointers() {
+        Python::with_gil(|py| {
+            let struct_types = StructTypes::new();
+            let mut vm = VirtualMachine::new(false);
+            let mut encoder = ArgsEncoder::new(&struct_types, &mut vm, false);
+            let err = encoder
+                .encode_pointer(1.to_object(py).as_ref(py), "felt", "a")
+                .unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("Expected a pointer, a list or a dict for argument 'a' of type felt*"));
+
+            let pointer = PyRelocatable::from((1, 2)).into_py(py);
+            assert_eq!(
+                encoder
+                    .encode_pointer(pointer.as_ref(py), "felt", "a")
+                    .unwrap(),
+                MaybeRelocatable::from((1, 2))
+            );
+        });
+    }
+
+    #[test]
+    fn strings_are_not_sequences() {
+        Python::with_gil(|py| {
+            assert!(sequence_items("abc".to_object(py).as_ref(py)).is_err());
+            let items = vec![1, 2].to_object(py);
+            assert_eq!(sequence_items(items.as_ref(py)).unwrap().len(), 2);
+        });
+    }
+}
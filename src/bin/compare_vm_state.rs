@@ -0,0 +1,58 @@
+//! Compares the trace or memory files of two runs, such as the ones written by
+//! cairo-lang and cairo-vm-py, reporting where they diverge.
+//!
+//! Usage: compare_vm_state <trace|memory> <expected file> <actual file>
+
+#[path = "../encoded_files.rs"]
+mod encoded_files;
+#[path = "../vm_state_diff.rs"]
+mod vm_state_diff;
+
+use std::{env, io, path::Path, process::ExitCode};
+use vm_state_diff::{diff_memory_files, diff_trace_files};
+
+const USAGE: &str = "Usage: compare_vm_state <trace|memory> <expected file> <actual file>";
+
+fn main() -> ExitCode {
+    let args = env::args().collect::<Vec<_>>();
+    let (kind, expected, actual) = match args.as_slice() {
+        [_, kind, expected, actual] => (kind.as_str(), Path::new(expected), Path::new(actual)),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match compare(kind, expected, actual) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the differences between both files, returning whether they match.
+fn compare(kind: &str, expected: &Path, actual: &Path) -> io::Result<bool> {
+    match kind {
+        "trace" => match diff_trace_files(expected, actual)? {
+            Some(diff) => {
+                println!("{diff}");
+                Ok(false)
+            }
+            None => Ok(true),
+        },
+        "memory" => {
+            let diffs = diff_memory_files(expected, actual)?;
+            if !diffs.is_empty() {
+                println!("Memory differs at {} addresses:", diffs.len());
+                for diff in diffs.iter() {
+                    println!("  {diff}");
+                }
+            }
+            Ok(diffs.is_empty())
+        }
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, USAGE)),
+    }
+}
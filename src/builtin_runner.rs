@@ -0,0 +1,120 @@
+use crate::relocatable::PyRelocatable;
+use cairo_vm::vm::{
+    runners::builtin_runner::BuiltinRunner, vm_core::VirtualMachine,
+    vm_memory::memory_segments::MemorySegmentManager,
+};
+use pyo3::prelude::*;
+
+const BUILTIN_NAME_SUFFIX: &str = "_builtin";
+
+/// A snapshot of a builtin runner's state.
+#[pyclass(name = "BuiltinRunner")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PyBuiltinRunner {
+    #[pyo3(get)]
+    name: String,
+    #[pyo3(get)]
+    base: PyRelocatable,
+    #[pyo3(get)]
+    stop_ptr: Option<PyRelocatable>,
+    #[pyo3(get)]
+    cells_per_instance: u32,
+    #[pyo3(get)]
+    ratio: Option<u32>,
+    #[pyo3(get)]
+    used_instances: Option<usize>,
+}
+
+impl PyBuiltinRunner {
+    /// `used_instances` is only available once the segment sizes are known,
+    /// which is after the run ended.
+    pub fn new(builtin: &BuiltinRunner, vm: &VirtualMachine) -> Self {
+        let (base, stop_ptr) = builtin.get_memory_segment_addresses();
+        PyBuiltinRunner {
+            name: builtin.name().to_string(),
+            base: PyRelocatable::from((base as isize, 0_usize)),
+            stop_ptr: stop_ptr.map(|stop_ptr| PyRelocatable::from((base as isize, stop_ptr))),
+            cells_per_instance: cells_per_instance(builtin),
+            ratio: builtin.ratio(),
+            used_instances: builtin.get_used_instances(&used_sizes(vm)).ok(),
+        }
+    }
+}
+
+// The builtin runners keep these private
+fn cells_per_instance(builtin: &BuiltinRunner) -> u32 {
+    match builtin {
+        BuiltinRunner::Output(_) | BuiltinRunner::RangeCheck(_) => 1,
+        BuiltinRunner::Signature(_) => 2,
+        BuiltinRunner::Hash(_) | BuiltinRunner::SegmentArena(_) => 3,
+        BuiltinRunner::Bitwise(_) => 5,
+        BuiltinRunner::Poseidon(_) => 6,
+        BuiltinRunner::EcOp(_) => 7,
+        BuiltinRunner::Keccak(_) => 16,
+    }
+}
+
+/// A segment manager holding only the VM's segment used sizes, which is what
+/// the builtin runners read them from. The sizes are only known once they
+/// were computed, e.g. when the run ended.
+pub(crate) fn used_sizes(vm: &VirtualMachine) -> MemorySegmentManager {
+    let mut segments = MemorySegmentManager::new();
+    let used_sizes = (0..)
+        .map_while(|index| vm.get_segment_used_size(index))
+        .collect::<Vec<_>>();
+    if !used_sizes.is_empty() {
+        segments.segment_used_sizes = Some(used_sizes);
+    }
+    segments
+}
+
+#[pymethods]
+impl PyBuiltinRunner {
+    pub fn __repr__(&self) -> String {
+        format!(
+            "BuiltinRunner(name={}, base={:?}, stop_ptr={:?}, cells_per_instance={}, ratio={:?}, used_instances={:?})",
+            self.name,
+            self.base,
+            self.stop_ptr,
+            self.cells_per_instance,
+            self.ratio,
+            self.used_instances,
+        )
+    }
+}
+
+/// Whether a builtin runner has the given name, which may omit the
+/// `_builtin` suffix (i.e. both `pedersen` and `pedersen_builtin` are valid).
+pub(crate) fn builtin_name_matches(builtin: &BuiltinRunner, name: &str) -> bool {
+    builtin.name() == name || builtin.name().strip_suffix(BUILTIN_NAME_SUFFIX) == Some(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cairo_vm::vm::runners::builtin_runner::{HashBuiltinRunner, HASH_BUILTIN_NAME};
+
+    #[test]
+    fn builtin_name_with_and_without_suffix() {
+        let builtin: BuiltinRunner = HashBuiltinRunner::new(Some(8), true).into();
+        assert!(builtin_name_matches(&builtin, HASH_BUILTIN_NAME));
+        assert!(builtin_name_matches(&builtin, "pedersen"));
+        assert!(!builtin_name_matches(&builtin, "pedersen_"));
+        assert!(!builtin_name_matches(&builtin, "bitwise"));
+    }
+
+    #[test]
+    fn builtin_runner_before_initialization() {
+        let vm = VirtualMachine::new(false);
+        let mut builtin: BuiltinRunner = HashBuiltinRunner::new(Some(8), true).into();
+        builtin.initialize_segments(&mut MemorySegmentManager::new());
+
+        let py_builtin = PyBuiltinRunner::new(&builtin, &vm);
+        assert_eq!(py_builtin.name, HASH_BUILTIN_NAME);
+        assert_eq!(py_builtin.base, PyRelocatable::from((0, 0)));
+        assert_eq!(py_builtin.stop_ptr, None);
+        assert_eq!(py_builtin.cells_per_instance, 3);
+        assert_eq!(py_builtin.ratio, Some(8));
+        assert_eq!(py_builtin.used_instances, None);
+    }
+}
@@ -0,0 +1,639 @@
+use crate::{
+    builtin_runner::used_sizes,
+    cairo_runner::{PyCairoRunner, PyExecutionResources},
+    program::PyProgram,
+    relocatable::{PyMaybeRelocatable, PyRelocatable},
+    utils::to_py_error,
+    vm_core::CAIRO_PRIME,
+};
+use cairo_vm::{
+    felt::Felt252,
+    types::relocatable::{MaybeRelocatable, Relocatable},
+    vm::{
+        runners::{
+            builtin_runner::{
+                BuiltinRunner, HASH_BUILTIN_NAME, OUTPUT_BUILTIN_NAME, SIGNATURE_BUILTIN_NAME,
+            },
+            cairo_runner::ExecutionResources,
+        },
+        vm_core::VirtualMachine,
+    },
+};
+use num_bigint::BigUint;
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{IntoPyDict, PyBytes},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+const CAIRO_PIE_VERSION: &str = "1.1";
+// cairo-vm always places the program and execution segments first
+const PROGRAM_SEGMENT_INDEX: isize = 0;
+const EXECUTION_SEGMENT_INDEX: isize = 1;
+const ADDR_SIZE_IN_BYTES: usize = 8;
+const FIELD_SIZE_IN_BYTES: usize = 32;
+const OFFSET_BIT_LENGTH: usize = 47;
+const MAX_REPORTED_MISMATCHES: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SegmentInfo {
+    pub index: isize,
+    pub size: usize,
+}
+
+/// The program without its identifiers, hints and debug info.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct StrippedProgram {
+    pub data: Vec<String>,
+    pub builtins: Vec<String>,
+    pub main: usize,
+    pub prime: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CairoPieMetadata {
+    pub program: StrippedProgram,
+    pub program_segment: SegmentInfo,
+    pub execution_segment: SegmentInfo,
+    pub ret_fp_segment: SegmentInfo,
+    pub ret_pc_segment: SegmentInfo,
+    pub builtin_segments: BTreeMap<String, SegmentInfo>,
+    pub extra_segments: Vec<SegmentInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+struct ExecutionResourcesJson {
+    n_steps: usize,
+    #[serde(default)]
+    n_memory_holes: usize,
+    builtin_instance_counter: BTreeMap<String, usize>,
+}
+
+/// A Cairo Position Independent Execution: the memory of a run with its
+/// segments left unrelocated, in the format used by cairo-lang.
+#[pyclass(unsendable, name = "CairoPie")]
+#[derive(Clone, Debug)]
+pub struct PyCairoPie {
+    pub(crate) metadata: CairoPieMetadata,
+    pub(crate) memory: Vec<(Relocatable, MaybeRelocatable)>,
+    pub(crate) additional_data: BTreeMap<String, Value>,
+    pub(crate) execution_resources: ExecutionResources,
+}
+
+#[pymethods]
+impl PyCairoPie {
+    /// Loads a PIE zip archive from either a path or a binary file object.
+    #[staticmethod]
+    pub fn from_file(py: Python, file: &PyAny) -> PyResult<Self> {
+        let zip = py.import("zipfile")?.call_method1("ZipFile", (file,))?;
+        let read = |name: &str| -> PyResult<Vec<u8>> {
+            zip.call_method1("read", (name,))?.extract::<Vec<u8>>()
+        };
+
+        let metadata: CairoPieMetadata = from_json(&read("metadata.json")?)?;
+        let additional_data = from_json(&read("additional_data.json")?)?;
+        let execution_resources: ExecutionResourcesJson =
+            from_json(&read("execution_resources.json")?)?;
+        let memory = deserialize_memory(&read("memory.bin")?)?;
+        zip.call_method0("close")?;
+
+        Ok(PyCairoPie {
+            metadata,
+            memory,
+            additional_data,
+            execution_resources: ExecutionResources {
+                n_steps: execution_resources.n_steps,
+                n_memory_holes: execution_resources.n_memory_holes,
+                builtin_instance_counter: execution_resources
+                    .builtin_instance_counter
+                    .into_iter()
+                    .collect(),
+            },
+        })
+    }
+
+    #[getter]
+    pub fn execution_resources(&self) -> PyExecutionResources {
+        PyExecutionResources::from(self.execution_resources.clone())
+    }
+
+    /// The PIE's metadata as stored in `metadata.json`.
+    #[getter]
+    pub fn metadata(&self, py: Python) -> PyResult<PyObject> {
+        let json = to_json(&self.metadata)?;
+        Ok(py.import("json")?.call_method1("loads", (json,))?.into())
+    }
+
+    /// The builtins' data kept outside of memory, keyed by builtin name.
+    #[getter]
+    pub fn additional_data(&self, py: Python) -> PyResult<PyObject> {
+        let json = to_json(&self.additional_data)?;
+        Ok(py.import("json")?.call_method1("loads", (json,))?.into())
+    }
+
+    /// Every (address, value) pair in the PIE's memory, sorted by address.
+    #[getter]
+    pub fn memory(&self, py: Python) -> Vec<(PyRelocatable, PyObject)> {
+        let mut memory = self
+            .memory
+            .iter()
+            .map(|(address, value)| {
+                (
+                    PyRelocatable::from(*address),
+                    PyMaybeRelocatable::from(value).to_object(py),
+                )
+            })
+            .collect::<Vec<_>>();
+        memory.sort_by_key(|(address, _)| (address.segment_index, address.offset));
+        memory
+    }
+
+    /// Re-executes the PIE's program from its main over the PIE's memory and
+    /// checks that the run yields the same segments, memory, builtin data and
+    /// resources. The memory written by hints comes from the PIE, so programs
+    /// with hints can be checked too.
+    #[pyo3(signature = (layout="all_cairo".to_string()))]
+    pub fn run_and_check(&self, layout: Option<String>) -> PyResult<()> {
+        let mut runner = PyCairoRunner::from_cairo_pie(self, layout)?;
+        runner
+            .cairo_run_py(false, None, None, None, None, None, true, false)
+            .map_err(|err| {
+                PyValueError::new_err(format!(
+                    "Cairo PIE memory doesn't match the re-execution: {err}"
+                ))
+            })?;
+        let rerun = runner.get_cairo_pie()?;
+
+        if rerun.metadata != self.metadata {
+            return Err(PyValueError::new_err(format!(
+                "Cairo PIE metadata doesn't match the re-execution: expected {}, got {}",
+                to_json(&self.metadata)?,
+                to_json(&rerun.metadata)?
+            )));
+        }
+
+        let expected = self.memory.iter().cloned().collect::<HashMap<_, _>>();
+        let actual = rerun.memory.iter().cloned().collect::<HashMap<_, _>>();
+        let mismatches = expected
+            .keys()
+            .chain(actual.keys())
+            .filter(|address| expected.get(address) != actual.get(address))
+            .map(|address| (address.segment_index, address.offset))
+            .collect::<BTreeSet<_>>();
+        if !mismatches.is_empty() {
+            let mut addresses = mismatches
+                .iter()
+                .take(MAX_REPORTED_MISMATCHES)
+                .map(|(segment_index, offset)| format!("{segment_index}:{offset}"))
+                .collect::<Vec<_>>();
+            if mismatches.len() > MAX_REPORTED_MISMATCHES {
+                addresses.push(format!(
+                    "and {} more",
+                    mismatches.len() - MAX_REPORTED_MISMATCHES
+                ));
+            }
+            return Err(PyValueError::new_err(format!(
+                "Cairo PIE memory doesn't match the re-execution at {}",
+                addresses.join(", ")
+            )));
+        }
+
+        if rerun.additional_data != self.additional_data {
+            return Err(PyValueError::new_err(
+                "Cairo PIE builtin additional data doesn't match the re-execution",
+            ));
+        }
+        if rerun.execution_resources != self.execution_resources {
+            return Err(PyValueError::new_err(format!(
+                "Cairo PIE execution resources don't match the re-execution: expected {:?}, got {:?}",
+                self.execution_resources, rerun.execution_resources
+            )));
+        }
+        Ok(())
+    }
+
+    /// Writes the PIE as a zip archive to either a path or a binary file object.
+    pub fn write_zip(&self, py: Python, file: &PyAny) -> PyResult<()> {
+        let zipfile = py.import("zipfile")?;
+        let kwargs = [("compression", zipfile.getattr("ZIP_DEFLATED")?)].into_py_dict(py);
+        let zip = zipfile
+            .getattr("ZipFile")?
+            .call((file, "w"), Some(kwargs))?;
+
+        let execution_resources = ExecutionResourcesJson {
+            n_steps: self.execution_resources.n_steps,
+            n_memory_holes: self.execution_resources.n_memory_holes,
+            builtin_instance_counter: self
+                .execution_resources
+                .builtin_instance_counter
+                .clone()
+                .into_iter()
+                .collect(),
+        };
+        let files = [
+            ("metadata.json", to_json(&self.metadata)?),
+            ("additional_data.json", to_json(&self.additional_data)?),
+            ("execution_resources.json", to_json(&execution_resources)?),
+            (
+                "version.json",
+                to_json(&json!({ "cairo_pie": CAIRO_PIE_VERSION }))?,
+            ),
+        ];
+        for (name, contents) in files {
+            zip.call_method1("writestr", (name, contents))?;
+        }
+        let memory = PyBytes::new(py, &serialize_memory(&self.memory));
+        zip.call_method1("writestr", ("memory.bin", memory))?;
+        zip.call_method0("close")?;
+        Ok(())
+    }
+}
+
+impl PyCairoPie {
+    /// Rebuilds the stripped program, with `main` as its only identifier.
+    pub(crate) fn program(&self) -> PyResult<PyProgram> {
+        let program = &self.metadata.program;
+        let program_json = json!({
+            "prime": program.prime,
+            "data": program.data,
+            "builtins": program.builtins,
+            "main_scope": "__main__",
+            "hints": {},
+            "identifiers": {
+                "__main__.main": { "type": "function", "pc": program.main, "decorators": [] }
+            },
+            "reference_manager": { "references": [] },
+            "attributes": [],
+            "debug_info": null,
+        });
+        PyProgram::from_json(to_json(&program_json)?.as_bytes(), Some("main"))
+    }
+
+    /// Loads the PIE's segments, memory and signatures into a VM initialized
+    /// to run the PIE's program from its main. The memory written by the
+    /// program's hints is then already in place when re-executing it.
+    pub(crate) fn load_into(&self, vm: &mut VirtualMachine) -> PyResult<()> {
+        for builtin in vm.get_builtin_runners().iter() {
+            let name = builtin.name().trim_end_matches("_builtin");
+            let base = builtin.get_memory_segment_addresses().0 as isize;
+            if self
+                .metadata
+                .builtin_segments
+                .get(name)
+                .map(|segment| segment.index)
+                != Some(base)
+            {
+                return Err(PyValueError::new_err(format!(
+                    "Cairo PIE segment of builtin {name} doesn't match the program's"
+                )));
+            }
+        }
+        if let Some(last_index) = self
+            .metadata
+            .extra_segments
+            .iter()
+            .map(|segment| segment.index)
+            .max()
+        {
+            // The extra segments come right after the builtin segments
+            while vm.add_memory_segment().segment_index < last_index {}
+        }
+
+        // Signatures go first, as the signature builtin checks them when
+        // its memory is written
+        if let Some(signatures) = self.additional_data.get("ecdsa_builtin") {
+            let signature_builtin = vm.get_signature_builtin().map_err(to_py_error)?;
+            for (address, signature) in parse_signatures(signatures)? {
+                signature_builtin
+                    .add_signature(address, &signature)
+                    .map_err(to_py_error)?;
+            }
+        }
+
+        for (address, value) in self.memory.iter() {
+            vm.insert_value(*address, value.clone()).map_err(|err| {
+                PyValueError::new_err(format!(
+                    "Cairo PIE memory doesn't match the program at {}:{}: {err}",
+                    address.segment_index, address.offset
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Builds the PIE of a finished run that started from the program's main.
+    pub(crate) fn from_vm(
+        vm: &mut VirtualMachine,
+        main: usize,
+        execution_resources: ExecutionResources,
+        signatures: &HashMap<Relocatable, (Felt252, Felt252)>,
+    ) -> PyResult<Self> {
+        vm.compute_segments_effective_sizes();
+        let segment_sizes = (0..)
+            .map_while(|index| vm.get_segment_used_size(index))
+            .collect::<Vec<_>>();
+        let segment_size = |index: isize| {
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| segment_sizes.get(index).copied())
+                .unwrap_or_default()
+        };
+
+        let mut builtins = Vec::new();
+        let mut builtin_segments = BTreeMap::new();
+        let mut additional_data = BTreeMap::new();
+        for builtin in vm.get_builtin_runners().iter() {
+            let (base, stop_ptr) = builtin.get_memory_segment_addresses();
+            let name = builtin.name().trim_end_matches("_builtin");
+            builtins.push(name.to_string());
+            builtin_segments.insert(
+                name.to_string(),
+                SegmentInfo {
+                    index: base as isize,
+                    size: stop_ptr.unwrap_or_else(|| segment_size(base as isize)),
+                },
+            );
+            additional_data.insert(
+                builtin.name().to_string(),
+                builtin_additional_data(vm, builtin, signatures)?,
+            );
+        }
+
+        // The main entrypoint is called with the builtins followed by the
+        // return fp and pc, each pointing to an empty segment of its own
+        let return_segment = |offset: usize| match vm.get_maybe(&Relocatable::from((
+            EXECUTION_SEGMENT_INDEX,
+            builtins.len() + offset,
+        ))) {
+            Some(MaybeRelocatable::RelocatableValue(ptr)) if ptr.offset == 0 => Ok(SegmentInfo {
+                index: ptr.segment_index,
+                size: 0,
+            }),
+            _ => Err(PyValueError::new_err(
+                "Cairo PIEs can only be built from runs started at the program's main",
+            )),
+        };
+        let ret_fp_segment = return_segment(0)?;
+        let ret_pc_segment = return_segment(1)?;
+
+        let program_size = segment_size(PROGRAM_SEGMENT_INDEX);
+        let mut data = Vec::with_capacity(program_size);
+        for offset in 0..program_size {
+            match vm.get_maybe(&Relocatable::from((PROGRAM_SEGMENT_INDEX, offset))) {
+                Some(MaybeRelocatable::Int(value)) => {
+                    data.push(format!("{:#x}", value.to_biguint()))
+                }
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "Program data at offset {offset} is not an integer"
+                    )))
+                }
+            }
+        }
+
+        let mut known_segments = builtin_segments
+            .values()
+            .map(|segment| segment.index)
+            .collect::<HashSet<_>>();
+        known_segments.extend([
+            PROGRAM_SEGMENT_INDEX,
+            EXECUTION_SEGMENT_INDEX,
+            ret_fp_segment.index,
+            ret_pc_segment.index,
+        ]);
+        let extra_segments = (0..segment_sizes.len() as isize)
+            .filter(|index| !known_segments.contains(index))
+            .map(|index| SegmentInfo {
+                index,
+                size: segment_size(index),
+            })
+            .collect();
+
+        let mut memory = Vec::new();
+        for (segment_index, size) in segment_sizes.iter().enumerate() {
+            for offset in 0..*size {
+                let address = Relocatable::from((segment_index as isize, offset));
+                if let Some(value) = vm.get_maybe(&address) {
+                    memory.push((address, value));
+                }
+            }
+        }
+
+        let metadata = CairoPieMetadata {
+            program: StrippedProgram {
+                data,
+                builtins,
+                main,
+                prime: format!("{:#x}", *CAIRO_PRIME),
+            },
+            program_segment: SegmentInfo {
+                index: PROGRAM_SEGMENT_INDEX,
+                size: program_size,
+            },
+            execution_segment: SegmentInfo {
+                index: EXECUTION_SEGMENT_INDEX,
+                size: vm.get_ap().offset,
+            },
+            ret_fp_segment,
+            ret_pc_segment,
+            builtin_segments,
+            extra_segments,
+        };
+
+        Ok(PyCairoPie {
+            metadata,
+            memory,
+            additional_data,
+            execution_resources,
+        })
+    }
+}
+
+/// The builtin data kept outside of memory, in the format used by cairo-lang.
+/// The builtin runners keep it private, so it is rebuilt from the memory and
+/// the signatures the hints added.
+fn builtin_additional_data(
+    vm: &VirtualMachine,
+    builtin: &BuiltinRunner,
+    signatures: &HashMap<Relocatable, (Felt252, Felt252)>,
+) -> PyResult<Value> {
+    let address = |ptr: &Relocatable| json!([ptr.segment_index, ptr.offset]);
+    Ok(match builtin.name() {
+        // The addresses of the hashes that were computed
+        HASH_BUILTIN_NAME => {
+            let instances = builtin
+                .get_used_instances(&used_sizes(vm))
+                .map_err(to_py_error)?;
+            (0..instances)
+                .map(|instance| Relocatable::from((builtin.base() as isize, 3 * instance + 2)))
+                .filter(|result| vm.get_maybe(result).is_some())
+                .map(|result| address(&result))
+                .collect()
+        }
+        // cairo-vm doesn't split the output in pages
+        OUTPUT_BUILTIN_NAME => json!({ "pages": {}, "attributes": {} }),
+        // The (r, s) signature of every signed address
+        SIGNATURE_BUILTIN_NAME => signatures
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(signed, (r, s))| json!([address(signed), [felt_to_json(r), felt_to_json(s)]]))
+            .collect(),
+        _ => Value::Null,
+    })
+}
+
+/// Reads back the signatures written by `builtin_additional_data`.
+fn parse_signatures(data: &Value) -> PyResult<Vec<(Relocatable, (Felt252, Felt252))>> {
+    let invalid = || PyValueError::new_err("Invalid ecdsa_builtin additional data");
+    let felt = |value: &Value| {
+        BigUint::parse_bytes(value.to_string().as_bytes(), 10)
+            .map(Felt252::from)
+            .ok_or_else(invalid)
+    };
+    data.as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|entry| match entry.as_array().map(Vec::as_slice) {
+            Some([address, signature]) => {
+                let address: (isize, usize) =
+                    serde_json::from_value(address.clone()).map_err(|_| invalid())?;
+                match signature.as_array().map(Vec::as_slice) {
+                    Some([r, s]) => Ok((Relocatable::from(address), (felt(r)?, felt(s)?))),
+                    _ => Err(invalid()),
+                }
+            }
+            _ => Err(invalid()),
+        })
+        .collect()
+}
+
+/// Felts are written as plain JSON integers, as cairo-lang does.
+fn felt_to_json(value: &Felt252) -> Value {
+    Value::Number(serde_json::Number::from_string_unchecked(value.to_string()))
+}
+
+fn to_json<T: Serialize>(value: &T) -> PyResult<String> {
+    serde_json::to_string(value).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn from_json<T: DeserializeOwned>(json: &[u8]) -> PyResult<T> {
+    serde_json::from_slice(json).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+/// Serializes memory as consecutive (address, value) pairs of little endian
+/// integers, where relocatable values have their top bit set.
+pub(crate) fn serialize_memory(memory: &[(Relocatable, MaybeRelocatable)]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(memory.len() * (ADDR_SIZE_IN_BYTES + FIELD_SIZE_IN_BYTES));
+    for (address, value) in memory {
+        bytes.extend(relocatable_to_bytes(address, ADDR_SIZE_IN_BYTES));
+        match value {
+            MaybeRelocatable::RelocatableValue(ptr) => {
+                bytes.extend(relocatable_to_bytes(ptr, FIELD_SIZE_IN_BYTES))
+            }
+            MaybeRelocatable::Int(value) => {
+                bytes.extend(to_bytes_le(&value.to_biguint(), FIELD_SIZE_IN_BYTES))
+            }
+        }
+    }
+    bytes
+}
+
+pub(crate) fn deserialize_memory(bytes: &[u8]) -> PyResult<Vec<(Relocatable, MaybeRelocatable)>> {
+    let entry_size = ADDR_SIZE_IN_BYTES + FIELD_SIZE_IN_BYTES;
+    if bytes.len() % entry_size != 0 {
+        return Err(PyValueError::new_err(format!(
+            "Memory size {} is not a multiple of {entry_size}",
+            bytes.len()
+        )));
+    }
+
+    let mut memory = Vec::with_capacity(bytes.len() / entry_size);
+    for entry in bytes.chunks_exact(entry_size) {
+        let (address, value) = entry.split_at(ADDR_SIZE_IN_BYTES);
+        let address = relocatable_from_bytes(address)
+            .ok_or_else(|| PyValueError::new_err(format!("Invalid memory address {address:?}")))?;
+        let value = match relocatable_from_bytes(value) {
+            Some(ptr) => MaybeRelocatable::RelocatableValue(ptr),
+            None => MaybeRelocatable::Int(BigUint::from_bytes_le(value).into()),
+        };
+        memory.push((address, value));
+    }
+    Ok(memory)
+}
+
+/// Decodes a relocatable value, which is marked by the top bit being set.
+fn relocatable_from_bytes(bytes: &[u8]) -> Option<Relocatable> {
+    let value = BigUint::from_bytes_le(bytes);
+    let relocatable_bit = 8 * bytes.len() as u64 - 1;
+    if !value.bit(relocatable_bit) {
+        return None;
+    }
+    let offset_mask = (BigUint::from(1_u32) << OFFSET_BIT_LENGTH) - 1_u32;
+    let offset = usize::try_from(&value & offset_mask).ok()?;
+    let mut segment_index = value >> OFFSET_BIT_LENGTH;
+    segment_index.set_bit(relocatable_bit - OFFSET_BIT_LENGTH as u64, false);
+    Some(Relocatable::from((
+        isize::try_from(segment_index).ok()?,
+        offset,
+    )))
+}
+
+fn relocatable_to_bytes(ptr: &Relocatable, n_bytes: usize) -> Vec<u8> {
+    let value = (BigUint::from(1_u32) << (8 * n_bytes - 1))
+        + (BigUint::from(ptr.segment_index as usize) << OFFSET_BIT_LENGTH)
+        + BigUint::from(ptr.offset);
+    to_bytes_le(&value, n_bytes)
+}
+
+fn to_bytes_le(value: &BigUint, n_bytes: usize) -> Vec<u8> {
+    let mut bytes = value.to_bytes_le();
+    bytes.resize(n_bytes, 0);
+    bytes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn serialize_memory_entries() {
+        let memory = vec![
+            (
+                Relocatable::from((0, 1)),
+                MaybeRelocatable::Int(Felt252::new(0x0102_u32)),
+            ),
+            (
+                Relocatable::from((1, 0)),
+                MaybeRelocatable::RelocatableValue(Relocatable::from((2, 3))),
+            ),
+        ];
+        let bytes = serialize_memory(&memory);
+        assert_eq!(bytes.len(), 2 * (ADDR_SIZE_IN_BYTES + FIELD_SIZE_IN_BYTES));
+
+        // Address (0, 1) with the relocatable bit set
+        assert_eq!(bytes[..8], [1, 0, 0, 0, 0, 0, 0, 0x80]);
+        assert_eq!(bytes[8..10], [0x02, 0x01]);
+        assert!(bytes[10..40].iter().all(|byte| *byte == 0));
+
+        // Address (1, 0) holding the relocatable (2, 3)
+        assert_eq!(bytes[40..48], [0, 0, 0, 0, 0, 0x80, 0, 0x80]);
+        assert_eq!(bytes[48], 3);
+        assert_eq!(bytes[53], 0);
+        assert_eq!(bytes[54], 1);
+        assert_eq!(bytes[79], 0x80);
+
+        assert_eq!(deserialize_memory(&bytes).unwrap(), memory);
+    }
+
+    #[test]
+    fn deserialize_memory_with_invalid_entries() {
+        // Not a whole number of entries
+        assert!(deserialize_memory(&[0; 41]).is_err());
+        // Address without the relocatable bit set
+        assert!(deserialize_memory(&[0; 40]).is_err());
+    }
+}
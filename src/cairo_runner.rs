@@ -1,15 +1,34 @@
+#[cfg(feature = "cairo-1-hints")]
+use crate::casm_contract::CasmEntrypoint;
 use crate::{
+    abi::{self, ArgsEncoder},
+    builtin_runner::{builtin_name_matches, PyBuiltinRunner},
+    cairo_pie::PyCairoPie,
+    checkpoint::PyCheckpoint,
+    coverage::{CoverageTracker, PyCoverage},
+    debug_info::ProgramDebugInfo,
+    identifiers::ProgramIdentifiers,
     instruction_location::InstructionLocation,
     memory::PyMemory,
     memory_segments::PySegmentManager,
+    profiler::{FunctionMap, Profiler, PyProfile},
+    program::PyProgram,
     relocatable::{PyMaybeRelocatable, PyRelocatable},
     utils::to_py_error,
-    vm_core::PyVM,
+    vm_core::{format_hint_traceback, PyVM, CAIRO_PRIME},
 };
 use bincode::enc::write::Writer;
+#[cfg(feature = "cairo-1-hints")]
+use cairo_vm::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor;
 use cairo_vm::{
-    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
-    serde::deserialize_program::Member,
+    felt::Felt252,
+    hint_processor::{
+        builtin_hint_processor::builtin_hint_processor_definition::{
+            BuiltinHintProcessor, HintFunc,
+        },
+        hint_processor_definition::HintProcessor,
+    },
+    serde::deserialize_program::{BuiltinName, Member},
     types::{
         program::Program,
         relocatable::{MaybeRelocatable, Relocatable},
@@ -17,24 +36,51 @@ use cairo_vm::{
     vm::{
         errors::vm_exception::{get_error_attr_value, get_location, get_traceback},
         runners::{
-            builtin_runner::{HASH_BUILTIN_NAME, POSEIDON_BUILTIN_NAME, RANGE_CHECK_BUILTIN_NAME},
+            builtin_runner::{
+                HASH_BUILTIN_NAME, OUTPUT_BUILTIN_NAME, POSEIDON_BUILTIN_NAME,
+                RANGE_CHECK_BUILTIN_NAME,
+            },
             cairo_runner::{CairoRunner, ExecutionResources},
         },
         security::verify_secure_runner,
+        vm_core::VirtualMachine,
     },
 };
+use num_bigint::{BigInt, BigUint};
 use pyo3::{
-    exceptions::{PyNotImplementedError, PyTypeError, PyValueError},
+    exceptions::{PyTypeError, PyValueError},
     prelude::*,
-    types::PyIterator,
+    pyclass::CompareOp,
+    types::{PyBytes, PyDict, PyIterator},
 };
 use std::io::{self, Write};
-use std::{borrow::BorrowMut, collections::HashMap, path::PathBuf, rc::Rc};
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 pyo3::import_exception!(starkware.cairo.lang.vm.utils, ResourcesError);
 
 const MEMORY_GET_SEGMENT_USED_SIZE_MSG: &str = "Failed to segment used size";
 const FAILED_TO_GET_INITIAL_FP: &str = "Failed to get initial segment";
+const MEMORY_NOT_RELOCATED_MSG: &str = "Memory has not been relocated";
+
+/// Tells runners apart, e.g. to match checkpoints with the runner they came from.
+static NEXT_RUNNER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The data of every hint, keyed by the pc of the hints.
+type HintDataDictionary = HashMap<usize, Vec<Box<dyn Any>>>;
+
+/// Why the runner stopped stepping through the program.
+#[derive(Debug, PartialEq, Eq)]
+enum StopReason {
+    Breakpoint,
+    EndOfProgram,
+    StepLimit,
+}
 
 struct FileWriter {
     buf_writer: io::BufWriter<std::fs::File>,
@@ -69,15 +115,109 @@ impl FileWriter {
     }
 }
 
+#[derive(Default)]
+struct BytesWriter(Vec<u8>);
+
+impl Writer for BytesWriter {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
+        self.0.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// The calls that set up a runner before it runs. The CairoRunner's state
+/// can't be copied, so restoring a checkpoint replays them on a new one.
+#[derive(Clone)]
+pub(crate) enum SetupCall {
+    Entrypoint(String),
+    Initialize,
+    InitializeSegments,
+    FunctionRunner,
+    Cairo1FunctionRunner(Vec<BuiltinName>),
+    FunctionEntrypoint(usize, Vec<MaybeRelocatable>),
+}
+
+/// Creates a runner for `program` and makes the given setup calls on it. The
+/// VM they are made on is dropped, so segments added between them aren't
+/// accounted for: they only move the end of a function run, which matters
+/// just for the proof mode padding.
+fn set_up_runner(
+    program: &Program,
+    layout: &str,
+    proof_mode: bool,
+    setup: &[SetupCall],
+) -> PyResult<CairoRunner> {
+    let mut runner = CairoRunner::new(program, layout, proof_mode).map_err(to_py_error)?;
+    let mut vm = VirtualMachine::new(false);
+    for call in setup {
+        match call {
+            SetupCall::Entrypoint(entrypoint) => runner
+                .set_entrypoint(Some(entrypoint))
+                .map_err(to_py_error)?,
+            SetupCall::Initialize => {
+                runner.initialize(&mut vm).map_err(to_py_error)?;
+            }
+            SetupCall::InitializeSegments => runner.initialize_segments(&mut vm, None),
+            SetupCall::FunctionRunner => runner
+                .initialize_function_runner(&mut vm)
+                .map_err(to_py_error)?,
+            SetupCall::Cairo1FunctionRunner(builtins) => runner
+                .initialize_function_runner_cairo_1(&mut vm, builtins)
+                .map_err(to_py_error)?,
+            SetupCall::FunctionEntrypoint(entrypoint, stack) => {
+                runner
+                    .initialize_function_entrypoint(
+                        &mut vm,
+                        *entrypoint,
+                        stack.clone(),
+                        MaybeRelocatable::from(0),
+                    )
+                    .map_err(to_py_error)?;
+            }
+        }
+    }
+    Ok(runner)
+}
+
+/// The hint processor that runs the hints of a runner's program.
+enum RunnerHintProcessor {
+    Cairo0(BuiltinHintProcessor),
+    #[cfg(feature = "cairo-1-hints")]
+    Cairo1(Cairo1HintProcessor),
+}
+
+impl RunnerHintProcessor {
+    fn as_dyn(&mut self) -> &mut dyn HintProcessor {
+        match self {
+            RunnerHintProcessor::Cairo0(hint_processor) => hint_processor,
+            #[cfg(feature = "cairo-1-hints")]
+            RunnerHintProcessor::Cairo1(hint_processor) => hint_processor,
+        }
+    }
+}
+
 #[pyclass(unsendable)]
 #[pyo3(name = "CairoRunner")]
 pub struct PyCairoRunner {
+    id: usize,
     inner: CairoRunner,
+    layout: String,
+    proof_mode: bool,
+    setup: Vec<SetupCall>,
     pub(crate) pyvm: PyVM,
-    hint_processor: BuiltinHintProcessor,
+    hint_processor: RunnerHintProcessor,
+    #[cfg(feature = "cairo-1-hints")]
+    casm_entrypoint: Option<CasmEntrypoint>,
     hint_locals: HashMap<String, PyObject>,
     struct_types: Rc<HashMap<String, HashMap<String, Member>>>,
     static_locals: Option<HashMap<String, PyObject>>,
+    identifiers: Rc<ProgramIdentifiers>,
+    debug_info: Rc<ProgramDebugInfo>,
+    hint_data_dictionary: Option<Rc<HintDataDictionary>>,
+    end_pc: Option<Relocatable>,
+    /// Whether the runner was created already initialized, from a Cairo PIE.
+    initialized: bool,
+    breakpoints: HashSet<Relocatable>,
 }
 
 #[pymethods]
@@ -90,34 +230,89 @@ impl PyCairoRunner {
         layout: Option<String>,
         proof_mode: bool,
     ) -> PyResult<Self> {
-        let program =
-            Program::from_bytes(program.as_bytes(), entrypoint.as_deref()).map_err(to_py_error)?;
-        let cairo_runner = CairoRunner::new(
-            &program,
-            &layout.unwrap_or_else(|| "plain".to_string()),
+        let program = PyProgram::from_json(program.as_bytes(), entrypoint.as_deref())?;
+        PyCairoRunner::from_program(&program, None, layout, proof_mode)
+    }
+
+    /// Creates a runner for an already parsed Program, which can be shared by
+    /// many runners. The entrypoint defaults to the one the program was loaded with.
+    #[staticmethod]
+    #[pyo3(signature = (program, entrypoint=None, layout="plain".to_string(), proof_mode=false))]
+    pub fn from_program(
+        program: &PyProgram,
+        entrypoint: Option<&str>,
+        layout: Option<String>,
+        proof_mode: bool,
+    ) -> PyResult<Self> {
+        let layout = layout.unwrap_or_else(|| "plain".to_string());
+        let cairo_runner =
+            CairoRunner::new(&program.program, &layout, proof_mode).map_err(to_py_error)?;
+
+        let mut runner = PyCairoRunner::with_runner(
+            cairo_runner,
+            layout,
             proof_mode,
-        )
-        .map_err(to_py_error)?;
+            RunnerHintProcessor::Cairo0(BuiltinHintProcessor::new_empty()),
+            Rc::clone(&program.struct_types),
+            Rc::clone(&program.identifiers),
+            Rc::clone(&program.debug_info),
+        );
+        if let Some(entrypoint) = entrypoint {
+            runner.set_entrypoint(entrypoint)?;
+        }
+        Ok(runner)
+    }
 
-        let struct_types = program
-            .iter_identifiers()
-            .filter_map(|(path, identifier)| match identifier.type_.as_deref() {
-                Some("struct") => Some((path.to_string(), identifier.members.clone().unwrap())),
-                _ => None,
-            })
-            .collect();
+    /// Creates a runner for an entry point of a compiled Cairo 1 (CASM)
+    /// contract class, given its JSON as either str or bytes. The entry point
+    /// is run with `run_casm_entrypoint`.
+    #[cfg(feature = "cairo-1-hints")]
+    #[staticmethod]
+    #[pyo3(signature = (contract_class, entrypoint_selector, layout="all_cairo".to_string()))]
+    pub fn from_casm_contract(
+        contract_class: &PyAny,
+        entrypoint_selector: BigUint,
+        layout: Option<String>,
+    ) -> PyResult<Self> {
+        let (program, hint_processor, casm_entrypoint) =
+            CasmEntrypoint::load(contract_class, &entrypoint_selector)?;
+        let layout = layout.unwrap_or_else(|| "all_cairo".to_string());
+        let cairo_runner = CairoRunner::new(&program, &layout, false).map_err(to_py_error)?;
+
+        // Contract classes have no identifiers or debug info
+        let mut runner = PyCairoRunner::with_runner(
+            cairo_runner,
+            layout,
+            false,
+            RunnerHintProcessor::Cairo1(hint_processor),
+            Rc::new(HashMap::new()),
+            Rc::new(ProgramIdentifiers::new(&program, &HashMap::new())),
+            Rc::new(ProgramDebugInfo::new(None)?),
+        );
+        runner.casm_entrypoint = Some(casm_entrypoint);
+        Ok(runner)
+    }
 
-        Ok(PyCairoRunner {
-            inner: cairo_runner,
-            pyvm: PyVM::new(true),
-            hint_processor: BuiltinHintProcessor::new_empty(),
-            hint_locals: HashMap::new(),
-            struct_types: Rc::new(struct_types),
-            static_locals: None,
-        })
+    /// Creates a runner for the program stored in a Cairo PIE, initialized to
+    /// start at its main with the PIE's segments, memory and signatures already
+    /// loaded. PIEs don't keep the program's hints, so running it relies on
+    /// the loaded memory for the values they wrote.
+    #[staticmethod]
+    #[pyo3(signature = (cairo_pie, layout="all_cairo".to_string()))]
+    pub fn from_cairo_pie(cairo_pie: &PyCairoPie, layout: Option<String>) -> PyResult<Self> {
+        let program = cairo_pie.program()?;
+        let mut runner = PyCairoRunner::from_program(&program, Some("main"), layout, false)?;
+        runner.initialize()?;
+        cairo_pie.load_into(&mut (*runner.pyvm.vm).borrow_mut())?;
+        // The hints are compiled on the first step, once the PIE is loaded
+        runner.hint_data_dictionary = None;
+        runner.initialized = true;
+        Ok(runner)
     }
 
     #[pyo3(name = "cairo_run")]
+    #[pyo3(signature = (print_output, trace_file=None, memory_file=None, hint_locals=None, static_locals=None, entrypoint=None, trace_enabled=false, relocate_mem=false))]
+    #[allow(clippy::too_many_arguments)]
     pub fn cairo_run_py(
         &mut self,
         print_output: bool,
@@ -126,15 +321,23 @@ impl PyCairoRunner {
         hint_locals: Option<HashMap<String, PyObject>>,
         static_locals: Option<HashMap<String, PyObject>>,
         entrypoint: Option<&str>,
+        trace_enabled: bool,
+        relocate_mem: bool,
     ) -> PyResult<()> {
         if let Some(entrypoint) = entrypoint {
-            self.inner
-                .borrow_mut()
-                .set_entrypoint(Some(entrypoint))
-                .map_err(to_py_error)?;
+            if self.initialized {
+                return Err(PyValueError::new_err(
+                    "Can't set the entrypoint of a runner created from a Cairo PIE",
+                ));
+            }
+            self.set_entrypoint(entrypoint)?;
         }
 
-        let end = self.initialize()?;
+        // Runners created from a Cairo PIE come already initialized
+        let end = match self.end_pc {
+            Some(end) if self.initialized => end.into(),
+            _ => self.initialize()?,
+        };
 
         if let Some(locals) = hint_locals {
             self.hint_locals = locals
@@ -142,21 +345,14 @@ impl PyCairoRunner {
 
         self.static_locals = static_locals;
 
-        if trace_file.is_none() {
+        if trace_file.is_none() && !trace_enabled {
             (*self.pyvm.vm).borrow_mut().disable_trace();
         }
         if let Err(error) = self.run_until_pc(&end, None) {
             return Err(self.as_vm_exception(error));
         }
 
-        self.inner
-            .end_run(
-                false,
-                false,
-                &mut (*self.pyvm.vm).borrow_mut(),
-                &mut self.hint_processor,
-            )
-            .map_err(to_py_error)?;
+        self.end_run(false)?;
         (*self.pyvm.vm)
             .borrow()
             .verify_auto_deductions()
@@ -167,10 +363,10 @@ impl PyCairoRunner {
         verify_secure_runner(&self.inner, true, None, &mut (*self.pyvm.vm).borrow_mut())
             .map_err(to_py_error)?;
 
-        self.relocate(memory_file.is_some())?;
+        self.relocate(memory_file.is_some() || relocate_mem)?;
 
         if print_output {
-            self.write_output()?;
+            Python::with_gil(|py| self.write_output(py))?;
         }
 
         if let Some(trace_path) = trace_file {
@@ -203,15 +399,19 @@ impl PyCairoRunner {
     }
 
     pub fn initialize(&mut self) -> PyResult<PyRelocatable> {
-        self.inner
+        let end = self
+            .inner
             .initialize(&mut (*self.pyvm.vm).borrow_mut())
-            .map(PyRelocatable::from)
-            .map_err(to_py_error)
+            .map_err(to_py_error)?;
+        self.setup.push(SetupCall::Initialize);
+        self.end_pc = Some(end);
+        Ok(end.into())
     }
 
     pub fn initialize_segments(&mut self) {
         self.inner
-            .initialize_segments(&mut (*self.pyvm.vm).borrow_mut(), None)
+            .initialize_segments(&mut (*self.pyvm.vm).borrow_mut(), None);
+        self.setup.push(SetupCall::InitializeSegments);
     }
 
     pub fn run_until_pc(
@@ -219,25 +419,13 @@ impl PyCairoRunner {
         address: &PyRelocatable,
         run_resources_n_steps: Option<usize>,
     ) -> PyResult<()> {
-        let references = self.inner.get_reference_list();
-        let hint_data_dictionary = self
-            .inner
-            .get_hint_data_dictionary(&references, &mut self.hint_processor)
-            .map_err(to_py_error)?;
+        let hint_data_dictionary = self.hint_data_dictionary()?;
 
         let address = Into::<Relocatable>::into(address);
         let constants = self.inner.get_constants().clone();
         let mut steps_left = run_resources_n_steps.unwrap_or(1); // default value
         while self.pyvm.vm.borrow().get_pc() != address && steps_left > 0 {
-            self.pyvm.step(
-                &mut self.hint_processor,
-                &mut self.hint_locals,
-                &mut self.inner.exec_scopes,
-                &hint_data_dictionary,
-                Rc::clone(&self.struct_types),
-                &constants,
-                self.static_locals.as_ref(),
-            )?;
+            self.step_once(&hint_data_dictionary, &constants)?;
             // Consume step
             if run_resources_n_steps.is_some() {
                 steps_left -= 1;
@@ -251,6 +439,179 @@ impl PyCairoRunner {
         Ok(())
     }
 
+    /// Executes a single instruction, running its hints first.
+    pub fn step(&mut self) -> PyResult<()> {
+        let hint_data_dictionary = self.hint_data_dictionary()?;
+        let constants = self.inner.get_constants().clone();
+        self.step_once(&hint_data_dictionary, &constants)
+            .map_err(|error| self.as_vm_exception(error))
+    }
+
+    /// Executes up to `steps` instructions, stopping earlier if a breakpoint or
+    /// the end of the program is reached. Returns the number of executed steps.
+    /// A breakpoint at the pc the run starts from isn't hit, so that a run
+    /// stopped at a breakpoint can be resumed.
+    pub fn run_for(&mut self, steps: usize) -> PyResult<usize> {
+        let (steps, _) = self.run_until_stop(Some(steps))?;
+        Ok(steps)
+    }
+
+    /// Runs until a breakpoint is reached and returns its address, or None if
+    /// the program ended before hitting one. Raises ResourcesError if
+    /// `max_steps` were executed first. As with `run_for`, a breakpoint at the
+    /// pc the run starts from isn't hit.
+    #[pyo3(signature = (max_steps=None))]
+    pub fn run_until_breakpoint(
+        &mut self,
+        max_steps: Option<usize>,
+    ) -> PyResult<Option<PyRelocatable>> {
+        match self.run_until_stop(max_steps)? {
+            (_, StopReason::Breakpoint) => Ok(Some(self.pyvm.vm.borrow().get_pc().into())),
+            (_, StopReason::EndOfProgram) => Ok(None),
+            (steps, StopReason::StepLimit) => Err(ResourcesError::new_err(PyValueError::new_err(
+                format!("Error: No breakpoint was reached in {steps} steps."),
+            ))),
+        }
+    }
+
+    /// Adds a breakpoint given a pc offset, a RelocatableValue or a label name.
+    pub fn add_breakpoint(&mut self, location: &PyAny) -> PyResult<PyRelocatable> {
+        let address = self.breakpoint_address(location)?;
+        self.breakpoints.insert(address);
+        Ok(address.into())
+    }
+
+    /// Removes a breakpoint, returning whether it was set.
+    pub fn remove_breakpoint(&mut self, location: &PyAny) -> PyResult<bool> {
+        let address = self.breakpoint_address(location)?;
+        Ok(self.breakpoints.remove(&address))
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear()
+    }
+
+    #[getter]
+    pub fn breakpoints(&self) -> Vec<PyRelocatable> {
+        let mut breakpoints = self
+            .breakpoints
+            .iter()
+            .map(|address| PyRelocatable::from(*address))
+            .collect::<Vec<_>>();
+        breakpoints.sort_by_key(|address| (address.segment_index, address.offset));
+        breakpoints
+    }
+
+    /// Registers a callable to be run before (or after, if `after_step` is set)
+    /// every executed instruction. It's called with the current RunContext and
+    /// the decoded Instruction.
+    #[pyo3(signature = (hook, after_step=false))]
+    pub fn add_step_hook(&mut self, py: Python, hook: PyObject, after_step: bool) -> PyResult<()> {
+        if !hook.as_ref(py).is_callable() {
+            return Err(PyTypeError::new_err("step hook must be callable"));
+        }
+        if after_step {
+            self.pyvm.step_hooks.post_step.push(hook);
+        } else {
+            self.pyvm.step_hooks.pre_step.push(hook);
+        }
+        Ok(())
+    }
+
+    pub fn clear_step_hooks(&mut self) {
+        self.pyvm.step_hooks = Default::default();
+    }
+
+    /// Runs a callable instead of the hints whose code is `code`, taking
+    /// precedence over any other implementation. It's called with the VM, whose
+    /// only attribute is its `run_context`, the hint's `ids`, the memory and the
+    /// dict of the current scope's variables, which keeps its changes like the
+    /// globals of a hint.
+    pub fn register_hint(&mut self, py: Python, code: String, hint: PyObject) -> PyResult<()> {
+        if !hint.as_ref(py).is_callable() {
+            return Err(PyTypeError::new_err("hint implementation must be callable"));
+        }
+        self.pyvm.hint_functions.insert(code, hint);
+        // The hints were compiled before the implementation was registered
+        self.hint_data_dictionary = None;
+        Ok(())
+    }
+
+    /// Starts attributing every executed instruction to the function call
+    /// stack it runs in, discarding any previously collected profile.
+    pub fn enable_profiler(&mut self) {
+        let function_map = FunctionMap::new(&self.identifiers, Rc::clone(&self.debug_info));
+        self.pyvm.profiler = Some(Profiler::new(Rc::new(function_map)));
+    }
+
+    pub fn disable_profiler(&mut self) {
+        self.pyvm.profiler = None;
+    }
+
+    /// Returns the profile collected since the profiler was enabled.
+    pub fn get_profile(&self) -> PyResult<PyProfile> {
+        self.pyvm
+            .profiler
+            .as_ref()
+            .map(Profiler::profile)
+            .ok_or_else(|| PyValueError::new_err("Profiler is not enabled"))
+    }
+
+    /// Starts recording every executed pc and hint, discarding any previously
+    /// collected coverage.
+    pub fn enable_coverage(&mut self) {
+        self.pyvm.coverage = Some(CoverageTracker::default());
+    }
+
+    pub fn disable_coverage(&mut self) {
+        self.pyvm.coverage = None;
+    }
+
+    /// Returns the source-level coverage collected since coverage was enabled.
+    pub fn get_coverage(&self) -> PyResult<PyCoverage> {
+        self.pyvm
+            .coverage
+            .as_ref()
+            .map(|tracker| PyCoverage::new(tracker, &self.debug_info, &self.identifiers))
+            .ok_or_else(|| PyValueError::new_err("Coverage is not enabled"))
+    }
+
+    /// Snapshots the memory, registers, trace, builtins, execution scopes and
+    /// the runner's setup so that the run can later be rolled back with `restore`.
+    pub fn checkpoint(&mut self, py: Python) -> PyResult<PyCheckpoint> {
+        PyCheckpoint::new(
+            py,
+            self.id,
+            &self.setup,
+            self.end_pc,
+            &mut self.inner,
+            &mut self.pyvm,
+            &self.hint_locals,
+        )
+    }
+
+    /// Rolls the run back to a checkpoint taken from this runner. The runner
+    /// is set up again, so a run that ended since can be run again.
+    pub fn restore(&mut self, py: Python, checkpoint: &PyCheckpoint) -> PyResult<()> {
+        self.inner = checkpoint.restore(
+            py,
+            self.id,
+            |setup| {
+                set_up_runner(
+                    self.inner.get_program(),
+                    &self.layout,
+                    self.proof_mode,
+                    setup,
+                )
+            },
+            &mut self.pyvm,
+            &mut self.hint_locals,
+        )?;
+        self.setup = checkpoint.setup().to_vec();
+        self.end_pc = checkpoint.end_pc();
+        Ok(())
+    }
+
     pub fn mark_as_accessed(&mut self, address: PyRelocatable, size: usize) -> PyResult<()> {
         (*self.pyvm.vm)
             .borrow_mut()
@@ -264,16 +625,104 @@ impl PyCairoRunner {
             .map_err(to_py_error)
     }
 
-    pub fn write_output(&mut self) -> PyResult<()> {
-        let mut buffer = String::new();
-        (*self.pyvm.vm)
-            .borrow_mut()
-            .write_output(&mut buffer)
+    /// Returns the relocated trace as a list of (pc, ap, fp) tuples.
+    pub fn get_relocated_trace(&self) -> PyResult<Vec<(usize, usize, usize)>> {
+        Ok(self
+            .pyvm
+            .vm
+            .borrow()
+            .get_relocated_trace()
+            .map_err(to_py_error)?
+            .iter()
+            .map(|entry| (entry.pc, entry.ap, entry.fp))
+            .collect())
+    }
+
+    /// Returns the relocated memory as a mapping from address to value,
+    /// skipping the memory holes.
+    pub fn get_relocated_memory(&self) -> PyResult<HashMap<usize, BigUint>> {
+        Ok(self
+            .relocated_memory()?
+            .iter()
+            .enumerate()
+            .filter_map(|(address, value)| {
+                value.as_ref().map(|value| (address, value.to_biguint()))
+            })
+            .collect())
+    }
+
+    /// Returns the relocated memory encoded in the same binary format used
+    /// for the memory file.
+    pub fn get_relocated_memory_bytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let mut writer = BytesWriter::default();
+        cairo_vm::cairo_run::write_encoded_memory(self.relocated_memory()?, &mut writer)
             .map_err(to_py_error)?;
-        println!("Program Output:\n{}", buffer);
+        Ok(PyBytes::new(py, &writer.0))
+    }
+
+    /// Prints the program output through Python's `sys.stdout`, showing
+    /// values as signed felts like cairo-lang does.
+    pub fn write_output(&mut self, py: Python) -> PyResult<()> {
+        let mut buffer = String::from("Program Output:\n");
+        for value in self.get_output(true)? {
+            match value {
+                Some(value) => buffer.push_str(&format!("  {value}\n")),
+                None => buffer.push_str("  <missing>\n"),
+            }
+        }
+        buffer.push('\n');
+        py.import("sys")?
+            .getattr("stdout")?
+            .call_method1("write", (buffer,))?;
         Ok(())
     }
 
+    /// Returns the contents of the output builtin segment. Missing cells are
+    /// returned as None. If `signed` is set, values greater than `PRIME // 2`
+    /// are returned as negative numbers. Before the run ends the segment's size
+    /// isn't known yet, so it is read up to its first missing cell.
+    #[pyo3(signature = (signed=false))]
+    pub fn get_output(&self, signed: bool) -> PyResult<Vec<Option<BigInt>>> {
+        let vm = self.pyvm.vm.borrow();
+        let base = match vm
+            .get_builtin_runners()
+            .iter()
+            .find(|b| b.name() == OUTPUT_BUILTIN_NAME)
+        {
+            Some(builtin) => builtin.base(),
+            None => return Ok(Vec::new()),
+        };
+        // Computing the sizes mid-run would cache them while they are still growing
+        let size = vm.get_segment_used_size(base).unwrap_or_else(|| {
+            (0..)
+                .take_while(|offset| {
+                    vm.get_maybe(&Relocatable::from((base as isize, *offset)))
+                        .is_some()
+                })
+                .count()
+        });
+
+        let half_prime = &*CAIRO_PRIME >> 1_u32;
+        (0..size)
+            .map(|offset| {
+                let value = match vm.get_maybe(&Relocatable::from((base as isize, offset))) {
+                    Some(MaybeRelocatable::Int(value)) => value.to_biguint(),
+                    Some(MaybeRelocatable::RelocatableValue(_)) => {
+                        return Err(PyValueError::new_err(format!(
+                            "Output value at offset {offset} is not an integer"
+                        )))
+                    }
+                    None => return Ok(None),
+                };
+                Ok(Some(if signed && value > half_prime {
+                    BigInt::from(value) - BigInt::from(CAIRO_PRIME.clone())
+                } else {
+                    BigInt::from(value)
+                }))
+            })
+            .collect()
+    }
+
     pub fn add_segment(&self) -> PyRelocatable {
         (*self.pyvm.vm).borrow_mut().add_memory_segment().into()
     }
@@ -334,6 +783,24 @@ impl PyCairoRunner {
             .map_err(to_py_error)
     }
 
+    /// Returns the Cairo PIE of a finished run. The run should have the trace
+    /// enabled for the execution resources to count its steps.
+    pub fn get_cairo_pie(&self) -> PyResult<PyCairoPie> {
+        let main = self
+            .inner
+            .get_program()
+            .get_identifier("__main__.main")
+            .and_then(|identifier| identifier.pc)
+            .ok_or_else(|| PyValueError::new_err("The program has no main function"))?;
+        let execution_resources = self.get_execution_resources()?;
+        PyCairoPie::from_vm(
+            &mut (*self.pyvm.vm).borrow_mut(),
+            main,
+            execution_resources.0,
+            &self.pyvm.signatures,
+        )
+    }
+
     pub fn get_ap(&self) -> PyResult<PyRelocatable> {
         Ok(PyRelocatable::from(self.pyvm.vm.borrow().get_ap()))
     }
@@ -361,6 +828,36 @@ impl PyCairoRunner {
         Ok(return_values)
     }
 
+    /// Decodes the values returned by the given function following its
+    /// `Return` type, once it has run.
+    pub fn get_typed_return_values(&self, py: Python, function_name: &str) -> PyResult<PyObject> {
+        let pc = self.identifiers.get_function_pc(function_name)?;
+        let function = self
+            .identifiers
+            .function_name(pc)
+            .ok_or_else(|| PyValueError::new_err(format!("No function found at pc {pc}")))?;
+
+        let return_type = format!("{function}.Return");
+        let return_type = match self.identifiers.type_definition(&return_type) {
+            Some(cairo_type) => cairo_type.to_string(),
+            None if self.struct_types.contains_key(&return_type) => return_type,
+            None => {
+                return Err(PyValueError::new_err(format!(
+                    "Function {function} has no Return type"
+                )))
+            }
+        };
+
+        let size = abi::type_size(&self.struct_types, &return_type)?;
+        let return_values = self
+            .pyvm
+            .get_vm()
+            .borrow()
+            .get_return_values(size)
+            .map_err(to_py_error)?;
+        abi::decode_value(py, &self.struct_types, &return_values, &return_type)
+    }
+
     pub fn get_segment_used_size(&self, index: usize, py: Python) -> PyResult<PyObject> {
         Ok(self
             .pyvm
@@ -385,6 +882,7 @@ impl PyCairoRunner {
         program_segment_size: Option<usize>,
         run_resources: Option<PyRunResources>,
         apply_modulo_to_args: Option<bool>,
+        abi_args: Option<bool>,
     ) -> PyResult<()> {
         if let Some(locals) = hint_locals {
             self.hint_locals = locals
@@ -394,13 +892,23 @@ impl PyCairoRunner {
 
         let entrypoint = if let Ok(x) = entrypoint.extract::<usize>() {
             x
-        } else if entrypoint.extract::<String>().is_ok() {
-            return Err(PyNotImplementedError::new_err(()));
+        } else if let Ok(name) = entrypoint.extract::<String>() {
+            self.identifiers.get_function_pc(&name)?
         } else {
             return Err(PyTypeError::new_err("entrypoint must be int or str"));
         };
 
-        let stack = if typed_args.unwrap_or_default() {
+        let stack = if abi_args.unwrap_or_default() {
+            let function = self.identifiers.function_name(entrypoint).ok_or_else(|| {
+                PyValueError::new_err(format!("No function found at pc {entrypoint}"))
+            })?;
+            ArgsEncoder::new(
+                &self.struct_types,
+                &mut (*self.pyvm.vm).borrow_mut(),
+                apply_modulo_to_args.unwrap_or(true),
+            )
+            .encode_function_args(function, args.as_ref(py))?
+        } else if typed_args.unwrap_or_default() {
             let args = self
                 .gen_typed_args(py, args.to_object(py))
                 .map_err(to_py_error)?;
@@ -428,48 +936,13 @@ impl PyCairoRunner {
             stack
         };
 
-        let return_fp = MaybeRelocatable::from(0);
-        let end = self
-            .inner
-            .initialize_function_entrypoint(
-                &mut (*self.pyvm.vm).borrow_mut(),
-                entrypoint,
-                stack,
-                return_fp,
-            )
-            .map_err(to_py_error)?;
-
-        self.inner
-            .initialize_vm(&mut (*self.pyvm.vm).borrow_mut())
-            .map_err(to_py_error)?;
-
-        if let Err(error) = self.run_until_pc(
-            &end.into(),
+        self.run_function(
+            entrypoint,
+            stack,
+            verify_secure.unwrap_or(true),
+            program_segment_size,
             run_resources.and_then(|resource| resource.n_steps),
-        ) {
-            return Err(self.as_vm_exception(error));
-        }
-
-        self.inner
-            .end_run(
-                true,
-                false,
-                &mut (*self.pyvm.vm).borrow_mut(),
-                &mut self.hint_processor,
-            )
-            .map_err(to_py_error)?;
-
-        if verify_secure.unwrap_or(true) {
-            verify_secure_runner(
-                &self.inner,
-                false,
-                program_segment_size,
-                &mut (*self.pyvm.vm).borrow_mut(),
-            )
-            .map_err(to_py_error)?;
-        }
-
-        Ok(())
+        )
     }
 
     /// Inserts a value into a memory address given by a Relocatable value.
@@ -481,11 +954,59 @@ impl PyCairoRunner {
     }
 
     // Initialize all the builtins and segments.
-    #[pyo3(signature = (_add_segment_arena_builtin=false))]
-    pub fn initialize_function_runner(&mut self, _add_segment_arena_builtin: bool) -> PyResult<()> {
-        self.inner
-            .initialize_function_runner(&mut (*self.pyvm.vm).borrow_mut())
-            .map_err(to_py_error)
+    // The segment_arena builtin used by Cairo 1 contracts is added as the last of the program's
+    // builtins, so that get_builtins_final_stack checks its stop pointer too. As the returned
+    // stack depends on the function, run_from_entrypoint leaves that check to the caller.
+    #[pyo3(signature = (add_segment_arena_builtin=false))]
+    pub fn initialize_function_runner(&mut self, add_segment_arena_builtin: bool) -> PyResult<()> {
+        if !add_segment_arena_builtin {
+            self.inner
+                .initialize_function_runner(&mut (*self.pyvm.vm).borrow_mut())
+                .map_err(to_py_error)?;
+            self.setup.push(SetupCall::FunctionRunner);
+            return Ok(());
+        }
+        let mut builtins = self.inner.get_program_builtins().clone();
+        if !builtins.contains(&BuiltinName::segment_arena) {
+            builtins.push(BuiltinName::segment_arena);
+        }
+        self.initialize_cairo_1_function_runner(&builtins)
+    }
+
+    /// Runs the entry point of a runner created with `from_casm_contract` with
+    /// the given calldata, and returns the values of the resulting
+    /// `Span<felt252>`. Panics are raised as errors carrying the panic data.
+    /// The entry point gets `initial_gas`, or `usize::MAX` if not given, and
+    /// reads the gas costs of the pedersen, bitwise, ec_op, poseidon and
+    /// segment_arena builtins from `builtin_costs`, all zero if not given.
+    #[cfg(feature = "cairo-1-hints")]
+    #[pyo3(signature = (calldata, initial_gas=None, builtin_costs=None))]
+    pub fn run_casm_entrypoint(
+        &mut self,
+        calldata: Vec<BigUint>,
+        initial_gas: Option<usize>,
+        builtin_costs: Option<Vec<BigUint>>,
+    ) -> PyResult<Vec<BigUint>> {
+        let casm_entrypoint = self.casm_entrypoint.clone().ok_or_else(|| {
+            PyValueError::new_err("The runner wasn't created from a CASM contract class")
+        })?;
+        self.initialize_cairo_1_function_runner(&casm_entrypoint.builtins()?)?;
+        // The hints are compiled on the first step, once the entry point is set up
+        self.hint_data_dictionary = None;
+        let stack = casm_entrypoint.initial_stack(
+            &mut (*self.pyvm.vm).borrow_mut(),
+            calldata,
+            initial_gas,
+            builtin_costs,
+        )?;
+        self.run_function(
+            casm_entrypoint.entrypoint.offset,
+            stack,
+            true,
+            Some(casm_entrypoint.program_segment_size()),
+            None,
+        )?;
+        casm_entrypoint.result(&self.inner, &mut (*self.pyvm.vm).borrow_mut())
     }
 
     pub fn gen_arg(
@@ -583,7 +1104,8 @@ impl PyCairoRunner {
     /**  Converts typed arguments to cairo friendly ones
     The args received should be an iterable with an __annotations__ attribute with a values method
     which returns an iterable containing the types of each of the elements in args
-    These types should de TypePointer, TypeFelt or TypeStruct
+    These types should be TypePointer, TypeFelt, TypeCodeoffset, TypeStruct or TypeTuple, either as
+    classes or as instances of them. Structs and tuples are flattened into their members
     This method is meant to process starknet's current typed arguments structure and shouldnt be used in any other case
     **/
     fn gen_typed_args(&self, py: Python<'_>, args: Py<PyAny>) -> PyResult<PyObject> {
@@ -596,55 +1118,41 @@ impl PyCairoRunner {
 
         let mut cairo_args = Vec::new();
         for (value, field_type) in std::iter::zip(args_iter, annotation_values?) {
-            let type_str = format!("{:?}", field_type?);
-            let type_str = type_str
-                .rsplit('.')
-                .next()
-                .ok_or_else(|| PyTypeError::new_err("gen_typed_args: Failed to get arg type"))?
-                .trim_end_matches("'>");
-
-            if type_str == "TypePointer" || type_str == "TypeFelt" {
-                cairo_args.push(self.gen_arg(py, value?.to_object(py), true)?)
-            } else if type_str == "TypeStruct" {
-                cairo_args.extend(self.gen_typed_args(py, value?.to_object(py)));
-            } else {
-                return Err(PyValueError::new_err(format!(
-                    "Failed to generate typed arguments: {type_str:?} is not supported"
-                )));
-            }
+            self.gen_typed_arg(py, value?, field_type?, &mut cairo_args)?;
         }
 
         Ok(cairo_args.to_object(py))
     }
 
+    /// Returns the base of the builtin with the given name, which may omit the
+    /// `_builtin` suffix (i.e. `get_builtin_base("bitwise")`).
+    pub fn get_builtin_base(&self, name: &str) -> PyResult<PyRelocatable> {
+        self.builtin_base(name, name.strip_suffix("_builtin").unwrap_or(name))
+    }
+
     /// Returns a the hash builtin's base if present
     pub fn get_hash_builtin_base(&self) -> PyResult<PyRelocatable> {
-        let vm = (*self.pyvm.vm).borrow_mut();
-        vm.get_builtin_runners()
-            .iter()
-            .find(|b| b.name() == HASH_BUILTIN_NAME)
-            .ok_or_else(|| PyValueError::new_err("hash builtin not present"))
-            .map(|b| PyRelocatable::from((b.base() as isize, 0_usize)))
+        self.builtin_base(HASH_BUILTIN_NAME, "hash")
     }
 
     /// Returns a the poseidon builtin's base if present
     pub fn get_poseidon_builtin_base(&self) -> PyResult<PyRelocatable> {
-        let vm = (*self.pyvm.vm).borrow_mut();
-        vm.get_builtin_runners()
-            .iter()
-            .find(|b| b.name() == POSEIDON_BUILTIN_NAME)
-            .ok_or_else(|| PyValueError::new_err("poseidon builtin not present"))
-            .map(|b| PyRelocatable::from((b.base() as isize, 0_usize)))
+        self.builtin_base(POSEIDON_BUILTIN_NAME, "poseidon")
     }
 
     /// Returns a the range_check builtin's base if present
     pub fn get_range_check_builtin_base(&self) -> PyResult<PyRelocatable> {
-        let vm = (*self.pyvm.vm).borrow_mut();
+        self.builtin_base(RANGE_CHECK_BUILTIN_NAME, "range_check")
+    }
+
+    /// Maps the name of every builtin runner to a snapshot of its state.
+    #[getter]
+    pub fn builtin_runners(&self) -> HashMap<String, PyBuiltinRunner> {
+        let vm = self.pyvm.vm.borrow();
         vm.get_builtin_runners()
             .iter()
-            .find(|b| b.name() == RANGE_CHECK_BUILTIN_NAME)
-            .ok_or_else(|| PyValueError::new_err("range_check builtin not present"))
-            .map(|b| PyRelocatable::from((b.base() as isize, 0_usize)))
+            .map(|b| (b.name().to_string(), PyBuiltinRunner::new(b, &vm)))
+            .collect()
     }
 
     #[getter]
@@ -676,33 +1184,384 @@ impl PyCairoRunner {
 pyo3::import_exception!(starkware.cairo.lang.vm.vm_exceptions, VmException);
 
 impl PyCairoRunner {
-    fn as_vm_exception(&self, error: PyErr) -> PyErr {
-        let pc = self.pyvm.vm.borrow().get_pc().offset;
-        let instruction_location = get_location(pc, &self.inner, self.pyvm.failed_hint_index)
-            .map(InstructionLocation::from);
-        let error_attribute = get_error_attr_value(pc, &self.inner, &self.pyvm.vm.borrow());
-        let traceback = get_traceback(&self.pyvm.vm.borrow(), &self.inner);
-        VmException::new_err((
-            PyRelocatable::from((0, pc)),
-            instruction_location,
-            error,
-            error_attribute,
-            traceback,
-        ))
+    /// `label` names the builtin in the error raised when it's not present.
+    fn builtin_base(&self, name: &str, label: &str) -> PyResult<PyRelocatable> {
+        let vm = self.pyvm.vm.borrow();
+        vm.get_builtin_runners()
+            .iter()
+            .find(|b| builtin_name_matches(b, name))
+            .ok_or_else(|| PyValueError::new_err(format!("{label} builtin not present")))
+            .map(|b| PyRelocatable::from((b.base() as isize, 0_usize)))
     }
-}
-
-#[derive(Clone, FromPyObject)]
-pub struct PyRunResources {
-    n_steps: Option<usize>,
-}
 
-#[pyclass]
-pub struct PyExecutionResources(ExecutionResources);
+    /// Runs a Rust implementation instead of the hints whose code is `code`,
+    /// unless a Python one was registered for it.
+    pub fn add_hint(&mut self, code: String, hint_func: Rc<HintFunc>) {
+        match &mut self.hint_processor {
+            RunnerHintProcessor::Cairo0(hint_processor) => hint_processor.add_hint(code, hint_func),
+            // Cairo 1 hints aren't looked up by their code
+            #[cfg(feature = "cairo-1-hints")]
+            RunnerHintProcessor::Cairo1(_) => {}
+        }
+    }
 
-#[pymethods]
-impl PyExecutionResources {
-    #[getter]
+    fn gen_typed_arg(
+        &self,
+        py: Python<'_>,
+        value: &PyAny,
+        field_type: &PyAny,
+        cairo_args: &mut Vec<PyObject>,
+    ) -> PyResult<()> {
+        match cairo_type_name(field_type)? {
+            "TypeFelt" | "TypeCodeoffset" => {
+                cairo_args.push(self.gen_arg(py, value.into(), true)?)
+            }
+            "TypePointer" => cairo_args.push(self.gen_typed_pointer(py, value)?),
+            "TypeStruct" => cairo_args.extend(self.gen_flattened_args(py, value)?),
+            "TypeTuple" => {
+                if value.hasattr("__annotations__")? {
+                    // A named tuple
+                    cairo_args.extend(self.gen_flattened_args(py, value)?);
+                } else if let Ok(members) = field_type.getattr("members") {
+                    let elements = value.iter()?.collect::<PyResult<Vec<_>>>()?;
+                    let members = members.iter()?.collect::<PyResult<Vec<_>>>()?;
+                    if elements.len() != members.len() {
+                        return Err(PyValueError::new_err(format!(
+                            "Tuple has {} members, but {} values were given",
+                            members.len(),
+                            elements.len()
+                        )));
+                    }
+                    for (element, member) in std::iter::zip(elements, members) {
+                        self.gen_typed_arg(py, element, member.getattr("typ")?, cairo_args)?;
+                    }
+                } else {
+                    for element in value.iter()? {
+                        let element = element?;
+                        if element.hasattr("__annotations__")? {
+                            cairo_args.extend(self.gen_flattened_args(py, element)?);
+                        } else {
+                            cairo_args.push(self.gen_arg(py, element.into(), true)?);
+                        }
+                    }
+                }
+            }
+            type_name => {
+                return Err(PyValueError::new_err(format!(
+                    "Failed to generate typed arguments: {type_name:?} is not supported"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Pointers to structs, or to arrays of structs, are given as the struct
+    /// values, whose flattened members are written to a new segment.
+    fn gen_typed_pointer(&self, py: Python<'_>, value: &PyAny) -> PyResult<PyObject> {
+        if value.hasattr("__annotations__")? {
+            let members = self.gen_flattened_args(py, value)?;
+            return self.gen_arg(py, members.to_object(py), true);
+        }
+        let elements = match value.iter() {
+            Ok(iterator) => iterator.collect::<PyResult<Vec<_>>>()?,
+            Err(_) => return self.gen_arg(py, value.into(), true),
+        };
+
+        let mut is_struct_array = !elements.is_empty();
+        for element in elements.iter() {
+            is_struct_array &= element.hasattr("__annotations__")?;
+        }
+        if is_struct_array {
+            let mut members = Vec::new();
+            for element in elements {
+                members.extend(self.gen_flattened_args(py, element)?);
+            }
+            return self.gen_arg(py, members.to_object(py), true);
+        }
+        self.gen_arg(py, elements.to_object(py), true)
+    }
+
+    fn gen_flattened_args(&self, py: Python<'_>, value: &PyAny) -> PyResult<Vec<PyObject>> {
+        self.gen_typed_args(py, value.into())?.extract(py)
+    }
+
+    fn with_runner(
+        cairo_runner: CairoRunner,
+        layout: String,
+        proof_mode: bool,
+        hint_processor: RunnerHintProcessor,
+        struct_types: Rc<HashMap<String, HashMap<String, Member>>>,
+        identifiers: Rc<ProgramIdentifiers>,
+        debug_info: Rc<ProgramDebugInfo>,
+    ) -> Self {
+        PyCairoRunner {
+            id: NEXT_RUNNER_ID.fetch_add(1, Ordering::Relaxed),
+            inner: cairo_runner,
+            layout,
+            proof_mode,
+            setup: Vec::new(),
+            pyvm: PyVM::new(true),
+            hint_processor,
+            #[cfg(feature = "cairo-1-hints")]
+            casm_entrypoint: None,
+            hint_locals: HashMap::new(),
+            struct_types,
+            static_locals: None,
+            identifiers,
+            debug_info,
+            hint_data_dictionary: None,
+            end_pc: None,
+            initialized: false,
+            breakpoints: HashSet::new(),
+        }
+    }
+
+    /// Initializes all the builtins and segments, with `builtins` as the
+    /// program's builtins, in that order.
+    fn initialize_cairo_1_function_runner(&mut self, builtins: &[BuiltinName]) -> PyResult<()> {
+        self.inner
+            .initialize_function_runner_cairo_1(&mut (*self.pyvm.vm).borrow_mut(), builtins)
+            .map_err(to_py_error)?;
+        self.setup
+            .push(SetupCall::Cairo1FunctionRunner(builtins.to_vec()));
+        Ok(())
+    }
+
+    fn set_entrypoint(&mut self, entrypoint: &str) -> PyResult<()> {
+        self.inner
+            .set_entrypoint(Some(entrypoint))
+            .map_err(to_py_error)?;
+        self.setup
+            .push(SetupCall::Entrypoint(entrypoint.to_string()));
+        Ok(())
+    }
+
+    /// Ends the run, then marks the addresses accessed before the VM was
+    /// rebuilt from a checkpoint.
+    fn end_run(&mut self, disable_trace_padding: bool) -> PyResult<()> {
+        let mut vm = (*self.pyvm.vm).borrow_mut();
+        self.inner
+            .end_run(
+                disable_trace_padding,
+                false,
+                &mut vm,
+                self.hint_processor.as_dyn(),
+            )
+            .map_err(to_py_error)?;
+        for address in std::mem::take(&mut self.pyvm.accessed_addresses) {
+            vm.mark_address_range_as_accessed(address, 1)
+                .map_err(to_py_error)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the function at `entrypoint` with the given stack until it returns.
+    fn run_function(
+        &mut self,
+        entrypoint: usize,
+        stack: Vec<MaybeRelocatable>,
+        verify_secure: bool,
+        program_segment_size: Option<usize>,
+        run_resources_n_steps: Option<usize>,
+    ) -> PyResult<()> {
+        let return_fp = MaybeRelocatable::from(0);
+        let end = self
+            .inner
+            .initialize_function_entrypoint(
+                &mut (*self.pyvm.vm).borrow_mut(),
+                entrypoint,
+                stack.clone(),
+                return_fp,
+            )
+            .map_err(to_py_error)?;
+        self.setup
+            .push(SetupCall::FunctionEntrypoint(entrypoint, stack));
+        self.end_pc = Some(end);
+
+        self.inner
+            .initialize_vm(&mut (*self.pyvm.vm).borrow_mut())
+            .map_err(to_py_error)?;
+
+        if let Err(error) = self.run_until_pc(&end.into(), run_resources_n_steps) {
+            return Err(self.as_vm_exception(error));
+        }
+
+        self.end_run(true)?;
+
+        if verify_secure {
+            verify_secure_runner(
+                &self.inner,
+                false,
+                program_segment_size,
+                &mut (*self.pyvm.vm).borrow_mut(),
+            )
+            .map_err(to_py_error)?;
+        }
+
+        Ok(())
+    }
+
+    fn relocated_memory(&self) -> PyResult<&Vec<Option<Felt252>>> {
+        if self.inner.relocated_memory.is_empty() {
+            return Err(PyValueError::new_err(MEMORY_NOT_RELOCATED_MSG));
+        }
+        Ok(&self.inner.relocated_memory)
+    }
+
+    fn hint_data_dictionary(&mut self) -> PyResult<Rc<HintDataDictionary>> {
+        if let Some(hint_data_dictionary) = &self.hint_data_dictionary {
+            return Ok(Rc::clone(hint_data_dictionary));
+        }
+        let references = self.inner.get_reference_list();
+        let hint_data_dictionary = Rc::new(
+            self.inner
+                .get_hint_data_dictionary(&references, self.hint_processor.as_dyn())
+                .map_err(to_py_error)?,
+        );
+        self.hint_data_dictionary = Some(Rc::clone(&hint_data_dictionary));
+        Ok(hint_data_dictionary)
+    }
+
+    fn step_once(
+        &mut self,
+        hint_data_dictionary: &HintDataDictionary,
+        constants: &HashMap<String, Felt252>,
+    ) -> PyResult<()> {
+        self.pyvm.step(
+            self.hint_processor.as_dyn(),
+            &mut self.hint_locals,
+            &mut self.inner.exec_scopes,
+            hint_data_dictionary,
+            Rc::clone(&self.struct_types),
+            constants,
+            self.static_locals.as_ref(),
+        )
+    }
+
+    /// Steps until a breakpoint, the end of the program or the step limit is
+    /// reached, returning the executed steps and which one it was. The first
+    /// step is always executed so that a run can be resumed from the
+    /// breakpoint it stopped at.
+    fn run_until_stop(&mut self, max_steps: Option<usize>) -> PyResult<(usize, StopReason)> {
+        let hint_data_dictionary = self.hint_data_dictionary()?;
+        let constants = self.inner.get_constants().clone();
+        let mut steps = 0;
+        loop {
+            let pc = self.pyvm.vm.borrow().get_pc();
+            if self.end_pc == Some(pc) {
+                return Ok((steps, StopReason::EndOfProgram));
+            }
+            if steps > 0 && self.breakpoints.contains(&pc) {
+                return Ok((steps, StopReason::Breakpoint));
+            }
+            if max_steps == Some(steps) {
+                return Ok((steps, StopReason::StepLimit));
+            }
+            if let Err(error) = self.step_once(&hint_data_dictionary, &constants) {
+                return Err(self.as_vm_exception(error));
+            }
+            steps += 1;
+        }
+    }
+
+    fn breakpoint_address(&self, location: &PyAny) -> PyResult<Relocatable> {
+        if let Ok(address) = location.extract::<PyRelocatable>() {
+            return Ok(Relocatable::from(&address));
+        }
+        let offset = if let Ok(offset) = location.extract::<usize>() {
+            offset
+        } else if let Ok(label) = location.extract::<String>() {
+            self.identifiers.get_function_pc(&label)?
+        } else {
+            return Err(PyTypeError::new_err(
+                "breakpoint must be int, str or RelocatableValue",
+            ));
+        };
+        let program_base = self
+            .inner
+            .program_base
+            .unwrap_or_else(|| Relocatable::from((0, 0)));
+        Ok(Relocatable::from((
+            program_base.segment_index,
+            program_base.offset + offset,
+        )))
+    }
+
+    fn as_vm_exception(&self, error: PyErr) -> PyErr {
+        let pc = self.pyvm.vm.borrow().get_pc().offset;
+        let instruction_location = get_location(pc, &self.inner, self.pyvm.failed_hint_index)
+            .map(InstructionLocation::from);
+        let error_attribute = get_error_attr_value(pc, &self.inner, &self.pyvm.vm.borrow());
+        let traceback = get_traceback(&self.pyvm.vm.borrow(), &self.inner);
+        Python::with_gil(|py| -> PyResult<PyErr> {
+            // A failed Python hint keeps its own traceback, both as the cause of
+            // the exception and as a note pointing at the hint's Cairo source
+            let hint_traceback = self
+                .pyvm
+                .failed_hint_index
+                .and_then(|hint_index| {
+                    self.debug_info
+                        .instruction_location(pc)?
+                        .hints
+                        .get(hint_index)
+                })
+                .map(|hint| format_hint_traceback(py, &error, hint))
+                .transpose()?
+                .flatten();
+            let kwargs = PyDict::new(py);
+            if let Some(hint_traceback) = hint_traceback {
+                kwargs.set_item("notes", vec![hint_traceback])?;
+            }
+
+            let exception = py.get_type::<VmException>().call(
+                (
+                    PyRelocatable::from((0, pc)),
+                    instruction_location,
+                    error.clone_ref(py),
+                    error_attribute,
+                    traceback,
+                ),
+                Some(kwargs),
+            )?;
+            let exception = PyErr::from_value(exception);
+            exception.set_cause(py, Some(error));
+            Ok(exception)
+        })
+        .unwrap_or_else(|err| err)
+    }
+}
+
+#[derive(Clone, FromPyObject)]
+pub struct PyRunResources {
+    n_steps: Option<usize>,
+}
+
+#[pyclass(module = "cairo_vm_py", name = "ExecutionResources")]
+pub struct PyExecutionResources(ExecutionResources);
+
+impl From<ExecutionResources> for PyExecutionResources {
+    fn from(resources: ExecutionResources) -> Self {
+        PyExecutionResources(resources)
+    }
+}
+
+#[pymethods]
+impl PyExecutionResources {
+    #[new]
+    #[pyo3(signature = (n_steps=0, builtin_instance_counter=None, n_memory_holes=0))]
+    fn new(
+        n_steps: usize,
+        builtin_instance_counter: Option<HashMap<String, usize>>,
+        n_memory_holes: usize,
+    ) -> Self {
+        PyExecutionResources(ExecutionResources {
+            n_steps,
+            n_memory_holes,
+            builtin_instance_counter: builtin_instance_counter.unwrap_or_default(),
+        })
+    }
+
+    #[getter]
     fn n_steps(&self) -> usize {
         self.0.n_steps
     }
@@ -716,15 +1575,152 @@ impl PyExecutionResources {
     fn builtin_instance_counter(&self) -> HashMap<String, usize> {
         self.0.builtin_instance_counter.clone()
     }
+
+    /// Sums the resources of two runs, adding up the counters of every builtin
+    /// used by either of them.
+    fn __add__(&self, other: &Self) -> Self {
+        let mut builtin_instance_counter = self.0.builtin_instance_counter.clone();
+        for (name, counter) in other.0.builtin_instance_counter.iter() {
+            *builtin_instance_counter.entry(name.clone()).or_default() += counter;
+        }
+        PyExecutionResources(ExecutionResources {
+            n_steps: self.0.n_steps + other.0.n_steps,
+            n_memory_holes: self.0.n_memory_holes + other.0.n_memory_holes,
+            builtin_instance_counter,
+        })
+    }
+
+    fn __sub__(&self, other: &Self) -> PyResult<Self> {
+        let sub = |a: usize, b: usize, resource: &str| {
+            a.checked_sub(b).ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "Cannot subtract {b} {resource} from {a}, resources can't be negative"
+                ))
+            })
+        };
+
+        let mut builtin_instance_counter = self.0.builtin_instance_counter.clone();
+        for (name, counter) in other.0.builtin_instance_counter.iter() {
+            let entry = builtin_instance_counter.entry(name.clone()).or_default();
+            *entry = sub(*entry, *counter, name.as_str())?;
+        }
+        Ok(PyExecutionResources(ExecutionResources {
+            n_steps: sub(self.0.n_steps, other.0.n_steps, "n_steps")?,
+            n_memory_holes: sub(
+                self.0.n_memory_holes,
+                other.0.n_memory_holes,
+                "n_memory_holes",
+            )?,
+            builtin_instance_counter,
+        }))
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python) -> PyObject {
+        let eq = self.0.n_steps == other.0.n_steps
+            && self.0.n_memory_holes == other.0.n_memory_holes
+            && self.0.builtin_instance_counter == other.0.builtin_instance_counter;
+        match op {
+            CompareOp::Eq => eq.into_py(py),
+            CompareOp::Ne => (!eq).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    /// Returns a copy without the builtins that weren't used.
+    fn filter_unused_builtins(&self) -> Self {
+        PyExecutionResources(ExecutionResources {
+            n_steps: self.0.n_steps,
+            n_memory_holes: self.0.n_memory_holes,
+            builtin_instance_counter: self
+                .0
+                .builtin_instance_counter
+                .iter()
+                .filter(|(_, counter)| **counter > 0)
+                .map(|(name, counter)| (name.clone(), *counter))
+                .collect(),
+        })
+    }
+
+    /// Returns the resources with the layout cairo-lang's `ExecutionResources`
+    /// is serialized with.
+    fn to_dict(&self, py: Python) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("n_steps", self.0.n_steps)?;
+        dict.set_item(
+            "builtin_instance_counter",
+            self.0.builtin_instance_counter.clone(),
+        )?;
+        dict.set_item("n_memory_holes", self.0.n_memory_holes)?;
+        Ok(dict.to_object(py))
+    }
+
+    #[staticmethod]
+    fn from_dict(resources: &PyDict) -> PyResult<Self> {
+        let n_steps = resources
+            .get_item("n_steps")
+            .ok_or_else(|| PyValueError::new_err("Missing n_steps"))?
+            .extract()?;
+        let builtin_instance_counter = resources
+            .get_item("builtin_instance_counter")
+            .map(|counter| counter.extract())
+            .transpose()?;
+        let n_memory_holes = resources
+            .get_item("n_memory_holes")
+            .map(|holes| holes.extract())
+            .transpose()?
+            .unwrap_or_default();
+        Ok(PyExecutionResources::new(
+            n_steps,
+            builtin_instance_counter,
+            n_memory_holes,
+        ))
+    }
+
+    fn __getnewargs__(&self) -> (usize, HashMap<String, usize>, usize) {
+        (
+            self.0.n_steps,
+            self.0.builtin_instance_counter.clone(),
+            self.0.n_memory_holes,
+        )
+    }
+
+    fn __repr__(&self) -> String {
+        let mut builtins = self.0.builtin_instance_counter.iter().collect::<Vec<_>>();
+        builtins.sort();
+        format!(
+            "ExecutionResources(n_steps={}, builtin_instance_counter={{{}}}, n_memory_holes={})",
+            self.0.n_steps,
+            builtins
+                .iter()
+                .map(|(name, counter)| format!("'{name}': {counter}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.0.n_memory_holes
+        )
+    }
+}
+
+/// Returns the name of a cairo type annotation, which may be either a type
+/// class (i.e. `TypeFelt`) or an instance of one (i.e. `TypePointer(...)`).
+fn cairo_type_name(field_type: &PyAny) -> PyResult<&str> {
+    match field_type.downcast::<pyo3::types::PyType>() {
+        Ok(class) => class.name(),
+        Err(_) => field_type.get_type().name(),
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::biguint;
     use crate::relocatable::PyMaybeRelocatable::RelocatableValue;
+    use crate::{any_box, biguint};
     use cairo_vm::felt::Felt252;
+    use cairo_vm::hint_processor::builtin_hint_processor::dict_manager::{
+        DictManager, DictTracker,
+    };
+    use cairo_vm::vm::runners::builtin_runner::SEGMENT_ARENA_BUILTIN_NAME;
     use num_bigint::BigUint;
+    use std::cell::RefCell;
     use std::env::temp_dir;
     use std::fs;
 
@@ -738,9 +1734,8 @@ mod test {
         B: An object that has an __annotations__ attribute
         C: The __annotations__  attribute should have a values method
         D: Values must return an iterable object containing the arg's type for each of the elements in args
-        F: This iterable object must yield the following format string when format!("{:?}") is applied to it:
-            **.Type or **.Type'>
-        Where Type can be either TypeFelt, TypePointer or TypeStruct
+        F: The class of each of these types must be named after the cairo type:
+            TypeFelt, TypePointer, TypeStruct, TypeCodeoffset or TypeTuple
         */
 
         // We first create the iterable pyclass (A)
@@ -770,20 +1765,31 @@ mod test {
         #[pyclass(unsendable)]
         pub struct Annotations(Vec<PyType>);
 
-        // We implement the values method (C), which in turn returns another object so that we can override its representation
+        // We implement the values method (C), which returns an instance of the matching type class (F)
         #[pymethods]
         impl Annotations {
-            pub fn values(&self) -> PyResult<Vec<PyType>> {
-                Ok(self.0.clone())
+            pub fn values(&self, py: Python) -> Vec<PyObject> {
+                self.0
+                    .iter()
+                    .map(|py_type| match py_type {
+                        PyType::TypePointer => TypePointer.into_py(py),
+                        PyType::TypeFelt => TypeFelt.into_py(py),
+                        PyType::TypeStruct => TypeStruct.into_py(py),
+                        PyType::TypeCodeoffset => TypeCodeoffset.into_py(py),
+                        PyType::TypeTuple => TypeTuple.into_py(py),
+                        PyType::BigInt => 0.into_py(py),
+                    })
+                    .collect()
             }
         }
 
-        #[pyclass]
         #[derive(Clone)]
         pub enum PyType {
             TypePointer,
             TypeFelt,
             TypeStruct,
+            TypeCodeoffset,
+            TypeTuple,
             // this value is added to test invalid types
             BigInt,
         }
@@ -792,37 +1798,21 @@ mod test {
         #[derive(Clone)]
         pub struct TypeFelt;
 
-        // We override the __repr__ method, so that we can customize the string we get when calling format!({:?}) (F)
-        #[pymethods]
-        impl TypeFelt {
-            fn __repr__(&self) -> String {
-                "TypeFelt".to_string()
-            }
-        }
-
         #[pyclass]
         #[derive(Clone)]
         pub struct TypePointer;
 
-        // We override the __repr__ method, so that we can customize the string we get when calling format!({:?}) (F)
-        #[pymethods]
-        impl TypePointer {
-            fn __repr__(&self) -> String {
-                "TypePointer".to_string()
-            }
-        }
-
         #[pyclass]
         #[derive(Clone)]
         pub struct TypeStruct;
 
-        // We override the __repr__ method, so that we can customize the string we get when calling format!({:?}) (F)
-        #[pymethods]
-        impl TypeStruct {
-            fn __repr__(&self) -> String {
-                "TypeStruct".to_string()
-            }
-        }
+        #[pyclass]
+        #[derive(Clone)]
+        pub struct TypeCodeoffset;
+
+        #[pyclass]
+        #[derive(Clone)]
+        pub struct TypeTuple;
     }
 
     #[test]
@@ -863,87 +1853,196 @@ mod test {
             false,
         )
         .unwrap();
-        runner.write_output().unwrap();
+        Python::with_gil(|py| runner.write_output(py)).unwrap();
     }
 
     #[test]
-    fn get_ap() {
-        let path = "cairo_programs/fibonacci.json".to_string();
+    fn get_output() {
+        let path = "cairo_programs/array_sum.json".to_string();
         let program = fs::read_to_string(path).unwrap();
-        let runner = PyCairoRunner::new(
+        let mut runner = PyCairoRunner::new(
             program,
             Some("main".to_string()),
-            Some("small".to_string()),
+            Some("all_cairo".to_string()),
             false,
         )
         .unwrap();
-        assert_eq!(runner.get_ap().unwrap(), PyRelocatable::from((1, 0)));
+        assert_eq!(runner.get_output(false).unwrap(), vec![]);
+
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+        assert_eq!(
+            runner.get_output(false).unwrap(),
+            vec![Some(BigInt::from(50))]
+        );
     }
 
     #[test]
-    fn get_poseidon_builtin_base_no_poseidon() {
-        let path = "cairo_programs/fibonacci.json".to_string();
+    fn get_output_mid_run() {
+        let path = "cairo_programs/array_sum.json".to_string();
         let program = fs::read_to_string(path).unwrap();
         let mut runner = PyCairoRunner::new(
             program,
             Some("main".to_string()),
-            Some("small".to_string()),
+            Some("all_cairo".to_string()),
             false,
         )
         .unwrap();
         runner.initialize().unwrap();
-        assert!(runner.get_poseidon_builtin_base().is_err());
+        runner.run_for(5).unwrap();
+
+        assert_eq!(runner.get_output(false).unwrap(), vec![]);
+        assert_eq!(runner.pyvm.vm.borrow().get_segment_used_size(0), None);
+
+        runner.run_until_breakpoint(None).unwrap();
+        assert_eq!(
+            runner.get_output(false).unwrap(),
+            vec![Some(BigInt::from(50))]
+        );
     }
 
     #[test]
-    fn get_poseidon_builtin_base_with_poseidon() {
-        let path = "cairo_programs/fibonacci.json".to_string();
+    fn get_output_signed() {
+        let path = "cairo_programs/negative_output.json".to_string();
         let program = fs::read_to_string(path).unwrap();
         let mut runner = PyCairoRunner::new(
             program,
             Some("main".to_string()),
-            Some("small".to_string()),
+            Some("all_cairo".to_string()),
             false,
         )
         .unwrap();
-        runner.initialize_function_runner(false).unwrap(); // Has all builtins
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+
         assert_eq!(
-            runner.get_poseidon_builtin_base().unwrap(),
-            PyRelocatable::from((9, 0))
+            runner.get_output(true).unwrap(),
+            vec![Some(BigInt::from(7)), Some(BigInt::from(-3))]
+        );
+        assert_eq!(
+            runner.get_output(false).unwrap(),
+            vec![
+                Some(BigInt::from(7)),
+                Some(BigInt::from(CAIRO_PRIME.clone()) - BigInt::from(3))
+            ]
         );
     }
 
     #[test]
-    fn get_hash_builtin_base_no_hash() {
-        let path = "cairo_programs/fibonacci.json".to_string();
+    fn write_output_to_python_stdout() {
+        let path = "cairo_programs/negative_output.json".to_string();
         let program = fs::read_to_string(path).unwrap();
         let mut runner = PyCairoRunner::new(
             program,
             Some("main".to_string()),
-            Some("small".to_string()),
+            Some("all_cairo".to_string()),
             false,
         )
         .unwrap();
-        runner.initialize().unwrap();
-        assert!(runner.get_hash_builtin_base().is_err());
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+
+        Python::with_gil(|py| {
+            let sys = py.import("sys").unwrap();
+            let stdout = sys.getattr("stdout").unwrap();
+            let buffer = py
+                .import("io")
+                .unwrap()
+                .getattr("StringIO")
+                .unwrap()
+                .call0()
+                .unwrap();
+            sys.setattr("stdout", buffer).unwrap();
+            let result = runner.write_output(py);
+            sys.setattr("stdout", stdout).unwrap();
+            result.unwrap();
+
+            let printed: String = buffer.call_method0("getvalue").unwrap().extract().unwrap();
+            assert_eq!(printed, "Program Output:\n  7\n  -3\n\n");
+        });
     }
 
     #[test]
-    fn get_hash_builtin_base_with_hash() {
+    fn get_ap() {
         let path = "cairo_programs/fibonacci.json".to_string();
         let program = fs::read_to_string(path).unwrap();
-        let mut runner = PyCairoRunner::new(
+        let runner = PyCairoRunner::new(
             program,
             Some("main".to_string()),
             Some("small".to_string()),
             false,
         )
         .unwrap();
-        runner.initialize_function_runner(false).unwrap(); // Has all builtins
-        assert_eq!(
-            runner.get_hash_builtin_base().unwrap(),
-            PyRelocatable::from((2, 0))
-        );
+        assert_eq!(runner.get_ap().unwrap(), PyRelocatable::from((1, 0)));
+    }
+
+    #[test]
+    fn get_poseidon_builtin_base_no_poseidon() {
+        let path = "cairo_programs/fibonacci.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+        assert!(runner.get_poseidon_builtin_base().is_err());
+    }
+
+    #[test]
+    fn get_poseidon_builtin_base_with_poseidon() {
+        let path = "cairo_programs/fibonacci.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize_function_runner(false).unwrap(); // Has all builtins
+        assert_eq!(
+            runner.get_poseidon_builtin_base().unwrap(),
+            PyRelocatable::from((9, 0))
+        );
+    }
+
+    #[test]
+    fn get_hash_builtin_base_no_hash() {
+        let path = "cairo_programs/fibonacci.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+        assert!(runner.get_hash_builtin_base().is_err());
+    }
+
+    #[test]
+    fn get_hash_builtin_base_with_hash() {
+        let path = "cairo_programs/fibonacci.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize_function_runner(false).unwrap(); // Has all builtins
+        assert_eq!(
+            runner.get_hash_builtin_base().unwrap(),
+            PyRelocatable::from((2, 0))
+        );
     }
 
     #[test]
@@ -979,6 +2078,75 @@ mod test {
         );
     }
 
+    #[test]
+    fn get_builtin_base_by_name() {
+        let path = "cairo_programs/fibonacci.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        let error = runner.get_builtin_base("bitwise_builtin").unwrap_err();
+        assert_eq!(error.to_string(), "ValueError: bitwise builtin not present");
+        let error = runner.get_hash_builtin_base().unwrap_err();
+        assert_eq!(error.to_string(), "ValueError: hash builtin not present");
+
+        runner.initialize_function_runner(false).unwrap(); // Has all builtins
+        assert_eq!(
+            runner.get_builtin_base("range_check").unwrap(),
+            runner.get_range_check_builtin_base().unwrap()
+        );
+        assert_eq!(
+            runner.get_builtin_base("bitwise").unwrap(),
+            runner.get_builtin_base("bitwise_builtin").unwrap()
+        );
+        assert!(runner.get_builtin_base("ec_op").is_ok());
+        assert!(runner.get_builtin_base("unknown").is_err());
+    }
+
+    #[test]
+    fn builtin_runners_after_run() {
+        let path = "cairo_programs/array_sum.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("all_cairo".to_string()),
+            false,
+        )
+        .unwrap();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+
+        let builtin_runners = runner.builtin_runners();
+        let output = &builtin_runners[OUTPUT_BUILTIN_NAME];
+        Python::with_gil(|py| {
+            let output = output.clone().into_py(py);
+            let output = output.as_ref(py);
+            assert_eq!(
+                output
+                    .getattr("base")
+                    .unwrap()
+                    .extract::<PyRelocatable>()
+                    .unwrap(),
+                runner.get_builtin_base("output").unwrap()
+            );
+            assert_eq!(
+                output
+                    .getattr("used_instances")
+                    .unwrap()
+                    .extract::<Option<usize>>()
+                    .unwrap(),
+                Some(1)
+            );
+            assert!(!output.getattr("stop_ptr").unwrap().is_none());
+        });
+    }
+
     #[test]
     fn add_segment() {
         let path = "cairo_programs/get_builtins_initial_stack.json".to_string();
@@ -992,7 +2160,7 @@ mod test {
         .unwrap();
 
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .unwrap();
         let new_segment = runner.add_segment();
         assert_eq!(
@@ -1025,7 +2193,7 @@ mod test {
         .unwrap();
 
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .unwrap();
 
         let expected_output: Vec<PyMaybeRelocatable> = vec![RelocatableValue(PyRelocatable {
@@ -1057,7 +2225,7 @@ mod test {
         .unwrap();
 
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .unwrap();
 
         let expected_output: Vec<PyMaybeRelocatable> = vec![
@@ -1095,7 +2263,7 @@ mod test {
         .unwrap();
 
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .unwrap();
 
         let expected_output = PyRelocatable::from((1, 8));
@@ -1120,7 +2288,7 @@ mod test {
         .unwrap();
 
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .unwrap();
 
         let expected_output = PyRelocatable::from((1, 0));
@@ -1145,7 +2313,7 @@ mod test {
         .unwrap();
 
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .unwrap();
 
         assert_eq!(runner.pyvm.vm.borrow().get_ap(), Relocatable::from((1, 41)));
@@ -1184,7 +2352,7 @@ mod test {
         let mut runner =
             PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .unwrap();
         Python::with_gil(|py| {
             assert_eq!(
@@ -1205,7 +2373,7 @@ mod test {
         let mut runner =
             PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .unwrap();
         Python::with_gil(|py| {
             assert_eq!(
@@ -1226,7 +2394,7 @@ mod test {
         let mut runner =
             PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .unwrap();
 
         Python::with_gil(|py| assert!(runner.get_segment_used_size(100, py).is_err()));
@@ -1244,7 +2412,7 @@ mod test {
             PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
 
         runner
-            .cairo_run_py(false, None, None, hint_locals, None, None)
+            .cairo_run_py(false, None, None, hint_locals, None, None, false, false)
             .unwrap();
 
         Python::with_gil(|py| assert!(runner.get_segment_used_size(100, py).is_err()));
@@ -1279,6 +2447,7 @@ mod test {
                     None,
                     None,
                     None,
+                    None,
                 )
                 .unwrap();
         });
@@ -1319,6 +2488,7 @@ mod test {
                     None,
                     None,
                     Some(false),
+                    None,
                 )
                 .unwrap();
         });
@@ -1365,6 +2535,7 @@ mod test {
                     None,
                     None,
                     Some(false),
+                    None,
                 )
                 .unwrap();
         });
@@ -1408,6 +2579,7 @@ mod test {
                     None,
                     None,
                     None,
+                    None,
                 )
                 .is_err());
         });
@@ -1425,6 +2597,41 @@ mod test {
         )
         .unwrap();
 
+        runner
+            .initialize_function_runner(false)
+            .expect("Failed to initialize function runner");
+
+        Python::with_gil(|py| {
+            runner
+                .run_from_entrypoint(
+                    py,
+                    py.eval("'not_main'", None, None).unwrap(),
+                    Vec::<&PyAny>::new().to_object(py),
+                    None,
+                    None,
+                    Some(false),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn run_from_entrypoint_with_unknown_string_name() {
+        let path = "cairo_programs/not_main.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("plain".to_string()),
+            false,
+        )
+        .unwrap();
+
         runner
             .initialize_function_runner(false)
             .expect("Failed to initialize function runner");
@@ -1432,7 +2639,7 @@ mod test {
         Python::with_gil(|py| {
             let result = runner.run_from_entrypoint(
                 py,
-                py.eval("'not_main'", None, None).unwrap(),
+                py.eval("'not_mainn'", None, None).unwrap(),
                 Vec::<&PyAny>::new().to_object(py),
                 None,
                 None,
@@ -1441,12 +2648,34 @@ mod test {
                 None,
                 None,
                 None,
+                None,
             );
-            // using a named entrypoint in run_from_entrypoint is not implemented yet
-            assert_eq!(
-                format!("{:?}", result),
-                format!("{:?}", Err::<(), PyErr>(PyNotImplementedError::new_err(())))
-            );
+            let error = result.unwrap_err().to_string();
+            assert!(error.contains("Unknown entrypoint 'not_mainn'"));
+            assert!(error.contains("__main__.not_main"));
+        });
+    }
+
+    #[test]
+    fn failed_hint_keeps_its_traceback() {
+        let path = "cairo_programs/bad_programs/hint_exception.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner =
+            PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
+
+        let error = runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap_err();
+        Python::with_gil(|py| {
+            let message = error.value(py).to_string();
+            assert!(message.contains("hint_exception.cairo:4:"));
+            assert!(message.contains("Error at pc=0:"));
+            assert!(message.contains("hint_exception.cairo\", line 6, in <module>"));
+            assert!(message.contains("ValueError: y is 4"));
+
+            let cause = error.cause(py).unwrap();
+            assert!(cause.is_instance_of::<PyValueError>(py));
+            assert_eq!(cause.value(py).to_string(), "y is 4");
         });
     }
 
@@ -1479,6 +2708,7 @@ mod test {
                 None,
                 Some(PyRunResources { n_steps: Some(0) }),
                 None,
+                None,
             );
             assert!(result.is_err());
             assert!(format!("{:?}", result).contains("Execution reached the end of the program."));
@@ -1519,6 +2749,7 @@ mod test {
                 None,
                 None,
                 None,
+                None,
             );
             assert_eq!(
                 format!("{:?}", result),
@@ -1560,6 +2791,7 @@ mod test {
                     None,
                     None,
                     None,
+                    None,
                 )
                 .unwrap();
             assert!(!runner.hint_locals.is_empty());
@@ -1605,6 +2837,7 @@ mod test {
                     None,
                     None,
                     None,
+                    None,
                 )
                 .unwrap();
             assert!(!runner.static_locals.as_ref().unwrap().is_empty());
@@ -1654,6 +2887,7 @@ mod test {
                 None,
                 None,
                 None,
+                None,
             )
         };
         Python::with_gil(|py| {
@@ -1699,6 +2933,7 @@ mod test {
                 None,
                 None,
                 None,
+                None,
             );
 
             assert!(result.is_ok());
@@ -1716,73 +2951,252 @@ mod test {
     }
 
     #[test]
-    fn insert() {
-        let path = "cairo_programs/fibonacci.json".to_string();
+    fn run_from_entrypoint_with_abi_args() {
+        let path = "cairo_programs/abi_args.json".to_string();
         let program = fs::read_to_string(path).unwrap();
-        let runner = PyCairoRunner::new(program, Some("main".to_string()), None, true).unwrap();
-
-        (*runner.pyvm.get_vm()).borrow_mut().add_memory_segment();
-        runner
-            .insert(&(0, 0).into(), PyMaybeRelocatable::Int(biguint!(3_u32)))
-            .unwrap();
-        runner
-            .insert(&(0, 1).into(), PyMaybeRelocatable::Int(biguint!(4_u32)))
-            .unwrap();
-        runner
-            .insert(&(0, 2).into(), PyMaybeRelocatable::Int(biguint!(5_u32)))
-            .unwrap();
-        assert_eq!(
-            (*runner.pyvm.get_vm())
-                .borrow()
-                .get_continuous_range((0, 0).into(), 3),
-            Ok(vec![3.into(), 4.into(), 5.into(),]),
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("plain".to_string()),
+            false,
         )
-    }
+        .unwrap();
 
-    /// Test that `PyCairoRunner::insert()` fails when it should.
-    #[test]
-    fn insert_duplicate() {
-        let path = "cairo_programs/fibonacci.json".to_string();
-        let program = fs::read_to_string(path).unwrap();
-        let runner = PyCairoRunner::new(program, Some("main".to_string()), None, true).unwrap();
+        runner.initialize_segments();
 
-        (*runner.pyvm.get_vm()).borrow_mut().add_memory_segment();
-        runner
-            .insert(&(0, 0).into(), PyMaybeRelocatable::Int(biguint!(3_u32)))
-            .unwrap();
-        runner
-            .insert(&(0, 1).into(), PyMaybeRelocatable::Int(biguint!(4_u32)))
-            .unwrap();
-        runner
-            .insert(&(0, 0).into(), PyMaybeRelocatable::Int(biguint!(5_u32)))
-            .expect_err("insertion succeeded when it should've failed");
-        assert_eq!(
-            (*runner.pyvm.get_vm())
-                .borrow()
-                .get_continuous_range((0, 0).into(), 2),
-            Ok(vec![3.into(), 4.into(),]),
-        );
+        Python::with_gil(|py| {
+            let args = py
+                .eval(
+                    "{'calls': 0, 'a': 1, 'point': {'x': 2, 'y': 3}, \
+                      'points': [{'x': 4, 'y': 0}, (0, 5)], 'pair': (6, [7]), \
+                      'n_values': 2, 'values': [0, 8]}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            runner
+                .run_from_entrypoint(
+                    py,
+                    "sum_args".to_object(py).as_ref(py),
+                    args.into(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(true),
+                )
+                .unwrap();
+
+            let return_values = runner
+                .get_return_values(2, py)
+                .unwrap()
+                .extract::<Vec<PyMaybeRelocatable>>(py)
+                .unwrap();
+            assert_eq!(
+                return_values,
+                vec![
+                    PyMaybeRelocatable::from(biguint!(1_u32)),
+                    PyMaybeRelocatable::from(biguint!(36_u32)),
+                ]
+            );
+
+            let typed_return_values = runner.get_typed_return_values(py, "sum_args").unwrap();
+            assert_eq!(
+                typed_return_values
+                    .extract::<HashMap<String, BigUint>>(py)
+                    .unwrap(),
+                HashMap::from([("sum".to_string(), biguint!(36_u32))])
+            );
+        });
     }
 
     #[test]
-    fn get_initial_fp_test() {
-        let path = "cairo_programs/fibonacci.json".to_string();
+    fn get_typed_return_values_of_structs_and_tuples() {
+        let path = "cairo_programs/abi_args.json".to_string();
         let program = fs::read_to_string(path).unwrap();
         let mut runner = PyCairoRunner::new(
             program,
             Some("main".to_string()),
-            Some(String::from("all_cairo")),
+            Some("plain".to_string()),
             false,
         )
         .unwrap();
-        runner
-            .cairo_run_py(false, None, None, None, None, None)
-            .unwrap();
-        assert_eq! {
-            PyRelocatable::from((1,2)),
-            runner.initial_fp().unwrap()
-        };
-    }
+
+        runner.initialize_segments();
+
+        Python::with_gil(|py| {
+            let args = py
+                .eval("{'point': {'x': 1, 'y': 2}, 'values': [3]}", None, None)
+                .unwrap();
+            runner
+                .run_from_entrypoint(
+                    py,
+                    "swap".to_object(py).as_ref(py),
+                    args.into(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(true),
+                )
+                .unwrap();
+
+            let return_values = runner.get_typed_return_values(py, "swap").unwrap();
+            let return_values = return_values
+                .extract::<HashMap<String, &PyAny>>(py)
+                .unwrap();
+            assert_eq!(
+                return_values["swapped"]
+                    .extract::<HashMap<String, BigUint>>()
+                    .unwrap(),
+                HashMap::from([
+                    ("x".to_string(), biguint!(2_u32)),
+                    ("y".to_string(), biguint!(1_u32)),
+                ])
+            );
+
+            let (x, values) = return_values["pair"]
+                .extract::<(BigUint, PyRelocatable)>()
+                .unwrap();
+            assert_eq!(x, biguint!(1_u32));
+            assert_eq!(
+                runner
+                    .get(py, &values)
+                    .unwrap()
+                    .extract::<BigUint>(py)
+                    .unwrap(),
+                biguint!(3_u32)
+            );
+
+            assert!(runner
+                .get_typed_return_values(py, "not_a_function")
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn run_from_entrypoint_with_missing_or_extra_abi_args() {
+        let path = "cairo_programs/abi_args.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("plain".to_string()),
+            false,
+        )
+        .unwrap();
+
+        runner.initialize_segments();
+
+        Python::with_gil(|py| {
+            let mut run = |args: &str| {
+                runner
+                    .run_from_entrypoint(
+                        py,
+                        "sum_args".to_object(py).as_ref(py),
+                        py.eval(args, None, None).unwrap().into(),
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        Some(true),
+                    )
+                    .unwrap_err()
+                    .to_string()
+            };
+
+            let err = run("{'calls': 0, 'a': 1, 'point': {'x': 2}}");
+            assert!(err.contains("Missing argument 'point.y' of type felt"));
+
+            let err = run("[0, 1]");
+            assert!(err.contains("Missing argument 'point' of type __main__.Point"));
+
+            let err = run("{'calls': 0, 'a': 1, 'b': 2}");
+            assert!(err.contains("Unexpected argument 'b'"));
+            assert!(err.contains("a: felt, point: __main__.Point, points: __main__.Point*"));
+
+            let err = run("[0, 1, [2, 3], [], (4, []), 0, [], 5]");
+            assert!(err.contains("Unexpected argument at position 7"));
+        });
+    }
+
+    #[test]
+    fn insert() {
+        let path = "cairo_programs/fibonacci.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let runner = PyCairoRunner::new(program, Some("main".to_string()), None, true).unwrap();
+
+        (*runner.pyvm.get_vm()).borrow_mut().add_memory_segment();
+        runner
+            .insert(&(0, 0).into(), PyMaybeRelocatable::Int(biguint!(3_u32)))
+            .unwrap();
+        runner
+            .insert(&(0, 1).into(), PyMaybeRelocatable::Int(biguint!(4_u32)))
+            .unwrap();
+        runner
+            .insert(&(0, 2).into(), PyMaybeRelocatable::Int(biguint!(5_u32)))
+            .unwrap();
+        assert_eq!(
+            (*runner.pyvm.get_vm())
+                .borrow()
+                .get_continuous_range((0, 0).into(), 3),
+            Ok(vec![3.into(), 4.into(), 5.into(),]),
+        )
+    }
+
+    /// Test that `PyCairoRunner::insert()` fails when it should.
+    #[test]
+    fn insert_duplicate() {
+        let path = "cairo_programs/fibonacci.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let runner = PyCairoRunner::new(program, Some("main".to_string()), None, true).unwrap();
+
+        (*runner.pyvm.get_vm()).borrow_mut().add_memory_segment();
+        runner
+            .insert(&(0, 0).into(), PyMaybeRelocatable::Int(biguint!(3_u32)))
+            .unwrap();
+        runner
+            .insert(&(0, 1).into(), PyMaybeRelocatable::Int(biguint!(4_u32)))
+            .unwrap();
+        runner
+            .insert(&(0, 0).into(), PyMaybeRelocatable::Int(biguint!(5_u32)))
+            .expect_err("insertion succeeded when it should've failed");
+        assert_eq!(
+            (*runner.pyvm.get_vm())
+                .borrow()
+                .get_continuous_range((0, 0).into(), 2),
+            Ok(vec![3.into(), 4.into(),]),
+        );
+    }
+
+    #[test]
+    fn get_initial_fp_test() {
+        let path = "cairo_programs/fibonacci.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some(String::from("all_cairo")),
+            false,
+        )
+        .unwrap();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+        assert_eq! {
+            PyRelocatable::from((1,2)),
+            runner.initial_fp().unwrap()
+        };
+    }
 
     #[test]
     fn failed_to_get_initial_fp_test() {
@@ -1861,6 +3275,47 @@ mod test {
         });
     }
 
+    #[test]
+    fn initialize_function_runner_with_segment_arena() {
+        let path = "cairo_programs/fibonacci.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("all_cairo".to_string()),
+            false,
+        )
+        .unwrap();
+
+        runner
+            .initialize_function_runner(true)
+            .expect("Failed to initialize function runner");
+
+        assert!(runner
+            .builtin_runners()
+            .contains_key(SEGMENT_ARENA_BUILTIN_NAME));
+        let segment_arena_base = runner.get_builtin_base("segment_arena").unwrap();
+
+        Python::with_gil(|py| {
+            let initial_stack = runner
+                .get_builtins_initial_stack(py)
+                .extract::<Vec<Vec<PyMaybeRelocatable>>>(py)
+                .unwrap();
+            assert_eq!(initial_stack.len(), 9);
+            // The segment_arena builtin is added to the program's builtins
+            assert_eq!(
+                runner
+                    .get_program_builtins_initial_stack(py)
+                    .extract::<Vec<PyMaybeRelocatable>>(py)
+                    .unwrap(),
+                vec![RelocatableValue(PyRelocatable {
+                    segment_index: segment_arena_base.segment_index,
+                    offset: 3,
+                })]
+            );
+        });
+    }
+
     #[test]
     fn program_builtins_initial_stack_are_empty_when_no_program_builtins() {
         let path = "cairo_programs/fibonacci.json".to_string();
@@ -2009,6 +3464,8 @@ mod test {
                     Python::with_gil(|py| -> PyObject { 100.to_object(py) }),
                 )])),
                 None,
+                false,
+                false
             )
             .is_ok());
     }
@@ -2034,7 +3491,9 @@ mod test {
                     "__find_element_max_size".to_string(),
                     Python::with_gil(|py| -> PyObject { 1.to_object(py) }),
                 )])),
-                None
+                None,
+                false,
+                false
             )
             .is_err());
     }
@@ -2047,7 +3506,7 @@ mod test {
             PyCairoRunner::new(program, None, Some("small".to_string()), false).unwrap();
 
         runner
-            .cairo_run_py(false, None, None, None, None, Some("main"))
+            .cairo_run_py(false, None, None, None, None, Some("main"), false, false)
             .expect("Call to PyCairoRunner::cairo_run_py() failed.");
     }
 
@@ -2080,7 +3539,7 @@ mod test {
             .unwrap();
 
             runner
-                .cairo_run_py(false, None, None, None, None, None)
+                .cairo_run_py(false, None, None, None, None, None, false, false)
                 .expect("Call to PyCairoRunner::cairo_run_py");
 
             let mut ap = runner.get_ap().unwrap();
@@ -2277,18 +3736,157 @@ mod test {
                 types: vec![PyType::TypeStruct, PyType::TypeStruct],
             };
 
+            // Structs are flattened into their members
             let stack = runner.gen_typed_args(py, arg.into_py(py)).unwrap();
-            let stack = stack.extract::<Vec<Py<PyAny>>>(py).unwrap();
-            for value in stack.iter() {
-                let stack = value.extract::<Vec<PyMaybeRelocatable>>(py).unwrap();
-                assert_eq!(
-                    stack,
+            let stack = stack.extract::<Vec<PyMaybeRelocatable>>(py).unwrap();
+            assert_eq!(
+                stack,
+                vec![
+                    MaybeRelocatable::from((0, 0)).into(),
+                    MaybeRelocatable::from((0, 1)).into(),
+                    MaybeRelocatable::from((0, 0)).into(),
+                    MaybeRelocatable::from((0, 1)).into(),
+                ]
+            );
+        })
+    }
+
+    #[test]
+    fn gen_typed_args_type_codeoffset_and_tuple() {
+        //For documentation on how this test works see test submodule type_samples
+
+        let program = fs::read_to_string("cairo_programs/fibonacci.json").unwrap();
+        let runner = PyCairoRunner::new(program, None, None, false).unwrap();
+        Python::with_gil(|py| {
+            let arg = MyIterator {
+                iter: Box::new(
                     vec![
-                        MaybeRelocatable::from((0, 0)).into(),
-                        MaybeRelocatable::from((0, 1)).into(),
+                        3.to_object(py),
+                        py.eval("(1, 2)", None, None).unwrap().to_object(py),
                     ]
-                );
-            }
+                    .into_iter(),
+                ),
+                types: vec![PyType::TypeCodeoffset, PyType::TypeTuple],
+            };
+
+            let stack = runner.gen_typed_args(py, arg.into_py(py)).unwrap();
+            let stack = stack.extract::<Vec<PyMaybeRelocatable>>(py).unwrap();
+            assert_eq!(
+                stack,
+                vec![
+                    PyMaybeRelocatable::from(biguint!(3_u32)),
+                    PyMaybeRelocatable::from(biguint!(1_u32)),
+                    PyMaybeRelocatable::from(biguint!(2_u32)),
+                ]
+            );
+        })
+    }
+
+    #[test]
+    fn gen_typed_args_pointer_to_struct() {
+        //For documentation on how this test works see test submodule type_samples
+
+        let program = fs::read_to_string("cairo_programs/fibonacci.json").unwrap();
+        let runner = PyCairoRunner::new(program, None, None, false).unwrap();
+        Python::with_gil(|py| {
+            let point = MyIterator {
+                iter: Box::new(vec![1.to_object(py), 2.to_object(py)].into_iter()),
+                types: vec![PyType::TypeFelt, PyType::TypeFelt],
+            };
+            let arg = MyIterator {
+                iter: Box::new(vec![point.into_py(py)].into_iter()),
+                types: vec![PyType::TypePointer],
+            };
+
+            let stack = runner.gen_typed_args(py, arg.into_py(py)).unwrap();
+            let stack = stack.extract::<Vec<PyMaybeRelocatable>>(py).unwrap();
+            let ptr = match stack.as_slice() {
+                [RelocatableValue(ptr)] => ptr.clone(),
+                _ => panic!("Expected a single pointer, got {stack:?}"),
+            };
+            assert_eq!(
+                runner
+                    .get_range(py, &ptr, 2)
+                    .unwrap()
+                    .extract::<Vec<PyMaybeRelocatable>>(py)
+                    .unwrap(),
+                vec![
+                    PyMaybeRelocatable::from(biguint!(1_u32)),
+                    PyMaybeRelocatable::from(biguint!(2_u32)),
+                ]
+            );
+        })
+    }
+
+    #[test]
+    fn gen_typed_args_named_tuples_and_type_classes() {
+        let program = fs::read_to_string("cairo_programs/fibonacci.json").unwrap();
+        let runner = PyCairoRunner::new(program, None, None, false).unwrap();
+        Python::with_gil(|py| {
+            let globals = pyo3::types::PyDict::new(py);
+            py.run(
+                r#"
+from typing import NamedTuple
+
+class TypeFelt: pass
+class TypePointer: pass
+class TypeTuple:
+    def __init__(self, members):
+        self.members = members
+class Item:
+    def __init__(self, typ):
+        self.typ = typ
+
+class Point(NamedTuple):
+    x: TypeFelt
+    y: TypeFelt
+
+class Args(tuple):
+    __annotations__ = {
+        "point": TypeTuple([]),
+        "pair": TypeTuple([Item(TypeFelt), Item(TypePointer)]),
+    }
+
+args = Args((Point(1, 2), (3, [4, 5])))
+short_args = Args((Point(1, 2), (3,)))
+"#,
+                Some(globals),
+                None,
+            )
+            .unwrap();
+            let args = globals.get_item("args").unwrap();
+
+            let stack = runner.gen_typed_args(py, args.into()).unwrap();
+            let stack = stack.extract::<Vec<PyMaybeRelocatable>>(py).unwrap();
+            assert_eq!(
+                stack[..3],
+                [
+                    PyMaybeRelocatable::from(biguint!(1_u32)),
+                    PyMaybeRelocatable::from(biguint!(2_u32)),
+                    PyMaybeRelocatable::from(biguint!(3_u32)),
+                ]
+            );
+            let ptr = match &stack[3] {
+                RelocatableValue(ptr) => ptr.clone(),
+                value => panic!("Expected a pointer, got {value:?}"),
+            };
+            assert_eq!(
+                runner
+                    .get_range(py, &ptr, 2)
+                    .unwrap()
+                    .extract::<Vec<PyMaybeRelocatable>>(py)
+                    .unwrap(),
+                vec![
+                    PyMaybeRelocatable::from(biguint!(4_u32)),
+                    PyMaybeRelocatable::from(biguint!(5_u32)),
+                ]
+            );
+
+            let short_args = globals.get_item("short_args").unwrap();
+            let error = runner.gen_typed_args(py, short_args.into()).unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("Tuple has 2 members, but 1 values were given"));
         })
     }
 
@@ -2417,7 +4015,16 @@ mod test {
         _ = fs::remove_file(trace_path);
 
         runner
-            .cairo_run_py(false, Some(trace_path), None, None, None, None)
+            .cairo_run_py(
+                false,
+                Some(trace_path),
+                None,
+                None,
+                None,
+                None,
+                false,
+                false,
+            )
             .expect("Call to PyCairoRunner::cairo_run_py() failed.");
 
         // We simply check if file exists
@@ -2440,10 +4047,19 @@ mod test {
 
         let trace_path = "cairo_programs";
 
-        let result = runner.cairo_run_py(false, Some(trace_path), None, None, None, None);
-
-        assert!(result.is_err());
-    }
+        let result = runner.cairo_run_py(
+            false,
+            Some(trace_path),
+            None,
+            None,
+            None,
+            None,
+            false,
+            false,
+        );
+
+        assert!(result.is_err());
+    }
 
     #[test]
     fn cairo_run_with_memory_file() {
@@ -2463,7 +4079,16 @@ mod test {
         _ = fs::remove_file(memory_path);
 
         runner
-            .cairo_run_py(false, None, Some(memory_path), None, None, None)
+            .cairo_run_py(
+                false,
+                None,
+                Some(memory_path),
+                None,
+                None,
+                None,
+                false,
+                false,
+            )
             .expect("Call to PyCairoRunner::cairo_run_py() failed.");
 
         // We simply check if file exists
@@ -2472,6 +4097,58 @@ mod test {
         _ = fs::remove_file(memory_path);
     }
 
+    #[test]
+    fn get_relocated_trace_and_memory() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+
+        runner
+            .cairo_run_py(false, None, None, None, None, None, true, true)
+            .expect("Call to PyCairoRunner::cairo_run_py() failed.");
+
+        let trace = runner.get_relocated_trace().unwrap();
+        assert!(!trace.is_empty());
+        // Relocated addresses start at 1, with the program segment first
+        assert_eq!(trace[0].0, 1);
+
+        let memory = runner.get_relocated_memory().unwrap();
+        assert!(!memory.contains_key(&0));
+        assert!(memory.contains_key(&1));
+
+        Python::with_gil(|py| {
+            let memory_bytes = runner.get_relocated_memory_bytes(py).unwrap();
+            // Each cell is encoded as an 8 byte address and a 32 byte value
+            assert_eq!(memory_bytes.as_bytes().len(), memory.len() * 40);
+        });
+    }
+
+    #[test]
+    fn get_relocated_trace_and_memory_when_not_relocated() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .expect("Call to PyCairoRunner::cairo_run_py() failed.");
+
+        assert!(runner.get_relocated_trace().is_err());
+        assert!(runner.get_relocated_memory().is_err());
+    }
+
     #[test]
     fn cairo_run_with_nonexistent_memory_file() {
         let path = String::from("cairo_programs/fibonacci.json");
@@ -2486,7 +4163,16 @@ mod test {
 
         let memory_path = "cairo_programs";
 
-        let result = runner.cairo_run_py(false, None, Some(memory_path), None, None, None);
+        let result = runner.cairo_run_py(
+            false,
+            None,
+            Some(memory_path),
+            None,
+            None,
+            None,
+            false,
+            false,
+        );
 
         assert!(result.is_err());
     }
@@ -2503,7 +4189,7 @@ mod test {
         )
         .unwrap();
 
-        let result = runner.cairo_run_py(false, None, None, None, None, None);
+        let result = runner.cairo_run_py(false, None, None, None, None, None, false, false);
 
         assert!(result.is_ok());
 
@@ -2518,6 +4204,923 @@ mod test {
         );
     }
 
+    #[test]
+    fn write_cairo_pie_zip() {
+        let path = String::from("cairo_programs/array_sum.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, true, false)
+            .unwrap();
+
+        let cairo_pie = runner.get_cairo_pie().unwrap();
+        assert_eq!(cairo_pie.metadata.program.builtins, vec!["output"]);
+        assert_eq!(cairo_pie.metadata.builtin_segments["output"].size, 1);
+        assert!(cairo_pie.execution_resources().n_steps() > 0);
+
+        Python::with_gil(|py| {
+            let file = py.import("io").unwrap().call_method0("BytesIO").unwrap();
+            cairo_pie.write_zip(py, file).unwrap();
+
+            let zip = py
+                .import("zipfile")
+                .unwrap()
+                .call_method1("ZipFile", (file,))
+                .unwrap();
+            let mut names = zip
+                .call_method0("namelist")
+                .unwrap()
+                .extract::<Vec<String>>()
+                .unwrap();
+            names.sort();
+            assert_eq!(
+                names,
+                vec![
+                    "additional_data.json",
+                    "execution_resources.json",
+                    "memory.bin",
+                    "metadata.json",
+                    "version.json",
+                ]
+            );
+
+            let memory = zip
+                .call_method1("read", ("memory.bin",))
+                .unwrap()
+                .extract::<Vec<u8>>()
+                .unwrap();
+            assert_eq!(memory.len(), cairo_pie.memory.len() * 40);
+
+            let metadata = zip
+                .call_method1("read", ("metadata.json",))
+                .unwrap()
+                .extract::<Vec<u8>>()
+                .unwrap();
+            let metadata: serde_json::Value = serde_json::from_slice(&metadata).unwrap();
+            assert_eq!(
+                metadata["program"]["main"],
+                runner.identifiers.get_function_pc("main").unwrap()
+            );
+            assert_eq!(metadata["ret_fp_segment"]["size"], 0);
+        });
+    }
+
+    #[test]
+    fn cairo_pie_keeps_builtin_additional_data() {
+        let path = String::from("cairo_programs/ecdsa.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("all_cairo".to_string()),
+            false,
+        )
+        .unwrap();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, true, false)
+            .unwrap();
+
+        let cairo_pie = runner.get_cairo_pie().unwrap();
+        let additional_data = &cairo_pie.additional_data;
+        assert_eq!(
+            additional_data["output_builtin"],
+            serde_json::json!({ "pages": {}, "attributes": {} })
+        );
+        assert!(additional_data["pedersen_builtin"].is_array());
+        assert_eq!(
+            additional_data.keys().collect::<Vec<_>>(),
+            vec!["ecdsa_builtin", "output_builtin", "pedersen_builtin"]
+        );
+
+        let ecdsa_base = cairo_pie.metadata.builtin_segments["ecdsa"].index;
+        let r = "1839793652349538280924927302501143912227271479439798783640887258675143576352";
+        let s = "1819432147005223164874083361865404672584671743718628757598322238853218813979";
+        assert_eq!(
+            additional_data["ecdsa_builtin"].to_string(),
+            format!("[[[{ecdsa_base},0],[{r},{s}]]]")
+        );
+    }
+
+    #[test]
+    fn get_cairo_pie_of_function_run_fails() {
+        let path = "cairo_programs/array_sum.json".to_string();
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("plain".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize_segments();
+
+        Python::with_gil(|py| {
+            let args = vec![vec![1.to_object(py)].to_object(py), 1.to_object(py)];
+            runner
+                .run_from_entrypoint(
+                    py,
+                    "array_sum".to_object(py).as_ref(py),
+                    args.into_py(py),
+                    None,
+                    None,
+                    Some(false),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+        });
+        assert!(runner.get_cairo_pie().is_err());
+    }
+
+    #[test]
+    fn load_and_check_cairo_pie() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner =
+            PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, true, false)
+            .unwrap();
+        let cairo_pie = runner.get_cairo_pie().unwrap();
+
+        Python::with_gil(|py| {
+            let file = py.import("io").unwrap().call_method0("BytesIO").unwrap();
+            cairo_pie.write_zip(py, file).unwrap();
+            let loaded = PyCairoPie::from_file(py, file).unwrap();
+
+            assert_eq!(loaded.metadata, cairo_pie.metadata);
+            assert_eq!(loaded.memory, cairo_pie.memory);
+            assert_eq!(loaded.additional_data, cairo_pie.additional_data);
+            assert_eq!(loaded.execution_resources, cairo_pie.execution_resources);
+            assert_eq!(loaded.memory(py).len(), cairo_pie.memory.len());
+            loaded.run_and_check(None).unwrap();
+
+            let mut runner = PyCairoRunner::from_cairo_pie(&loaded, None).unwrap();
+            assert!(runner
+                .cairo_run_py(false, None, None, None, None, Some("main"), false, false)
+                .is_err());
+            runner
+                .cairo_run_py(false, None, None, None, None, None, false, false)
+                .unwrap();
+            assert_eq!(
+                runner.get_ap().unwrap(),
+                PyRelocatable::from((1, loaded.metadata.execution_segment.size))
+            );
+        });
+    }
+
+    #[test]
+    fn check_tampered_cairo_pie() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner =
+            PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, true, false)
+            .unwrap();
+        let mut cairo_pie = runner.get_cairo_pie().unwrap();
+
+        // The tampered value is loaded before re-executing, so the run itself fails
+        let (_, value) = cairo_pie.memory.last_mut().unwrap();
+        *value = MaybeRelocatable::from(Felt252::new(42));
+        let err = cairo_pie.run_and_check(None).unwrap_err();
+        assert!(err.to_string().contains("doesn't match the re-execution"));
+    }
+
+    #[test]
+    fn load_and_check_cairo_pie_with_hints() {
+        for (path, layout) in [
+            ("cairo_programs/array_sum.json", "small"),
+            ("cairo_programs/ecdsa.json", "all_cairo"),
+        ] {
+            let program = fs::read_to_string(path).unwrap();
+            let mut runner = PyCairoRunner::new(
+                program,
+                Some("main".to_string()),
+                Some(layout.to_string()),
+                false,
+            )
+            .unwrap();
+            runner
+                .cairo_run_py(false, None, None, None, None, None, false, false)
+                .unwrap();
+            let cairo_pie = runner.get_cairo_pie().unwrap();
+            cairo_pie.run_and_check(Some(layout.to_string())).unwrap();
+
+            // The PIE's memory is loaded before running
+            let loaded =
+                PyCairoRunner::from_cairo_pie(&cairo_pie, Some(layout.to_string())).unwrap();
+            let vm = loaded.pyvm.vm.borrow();
+            for (address, value) in cairo_pie.memory.iter() {
+                assert_eq!(vm.get_maybe(address).as_ref(), Some(value));
+            }
+        }
+    }
+
+    #[test]
+    fn execution_resources_arithmetic() {
+        let resources = |n_steps, builtins: &[(&str, usize)], n_memory_holes| {
+            PyExecutionResources::new(
+                n_steps,
+                Some(
+                    builtins
+                        .iter()
+                        .map(|(name, counter)| (name.to_string(), *counter))
+                        .collect(),
+                ),
+                n_memory_holes,
+            )
+        };
+        let a = resources(10, &[("output_builtin", 1), ("pedersen_builtin", 2)], 1);
+        let b = resources(5, &[("pedersen_builtin", 1), ("range_check_builtin", 3)], 0);
+
+        let sum = a.__add__(&b);
+        assert_eq!(sum.n_steps(), 15);
+        assert_eq!(sum.n_memory_holes(), 1);
+        assert_eq!(
+            sum.builtin_instance_counter(),
+            HashMap::from([
+                ("output_builtin".to_string(), 1),
+                ("pedersen_builtin".to_string(), 3),
+                ("range_check_builtin".to_string(), 3),
+            ])
+        );
+
+        let diff = sum.__sub__(&b).unwrap();
+        assert_eq!(diff.n_steps(), 10);
+        assert_eq!(
+            diff.builtin_instance_counter(),
+            HashMap::from([
+                ("output_builtin".to_string(), 1),
+                ("pedersen_builtin".to_string(), 2),
+                ("range_check_builtin".to_string(), 0),
+            ])
+        );
+        assert_eq!(
+            diff.filter_unused_builtins().builtin_instance_counter(),
+            a.builtin_instance_counter()
+        );
+        assert!(a.__sub__(&b).is_err());
+    }
+
+    #[test]
+    fn execution_resources_serialization_and_equality() {
+        Python::with_gil(|py| {
+            let resources = PyExecutionResources::new(
+                10,
+                Some(HashMap::from([("output_builtin".to_string(), 1)])),
+                2,
+            );
+            let dict = resources.to_dict(py).unwrap();
+            let from_dict =
+                PyExecutionResources::from_dict(dict.as_ref(py).downcast::<PyDict>().unwrap())
+                    .unwrap();
+            assert!(resources
+                .__richcmp__(&from_dict, CompareOp::Eq, py)
+                .extract::<bool>(py)
+                .unwrap());
+            assert!(resources
+                .__richcmp__(&PyExecutionResources::new(10, None, 2), CompareOp::Ne, py)
+                .extract::<bool>(py)
+                .unwrap());
+
+            // Unpickling looks the class up in its module
+            let module = PyModule::new(py, "cairo_vm_py").unwrap();
+            crate::cairo_vm_py(py, module).unwrap();
+            let sys_modules = py.import("sys").unwrap().getattr("modules").unwrap();
+            sys_modules.set_item("cairo_vm_py", module).unwrap();
+            assert!(module
+                .getattr("ExecutionResources")
+                .unwrap()
+                .is(py.get_type::<PyExecutionResources>()));
+
+            let pickle = py.import("pickle").unwrap();
+            let pickled = pickle
+                .call_method1("dumps", (Py::new(py, resources).unwrap(),))
+                .unwrap();
+            let unpickled = pickle
+                .call_method1("loads", (pickled,))
+                .unwrap()
+                .extract::<PyRef<PyExecutionResources>>()
+                .unwrap();
+            assert_eq!(unpickled.n_steps(), 10);
+            assert_eq!(unpickled.n_memory_holes(), 2);
+            assert_eq!(
+                unpickled.builtin_instance_counter(),
+                HashMap::from([("output_builtin".to_string(), 1)])
+            );
+        });
+    }
+
+    #[test]
+    fn step_advances_pc() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+
+        let pc_before_step = runner.pyvm.vm.borrow().get_pc();
+        runner.step().unwrap();
+        assert_ne!(runner.pyvm.vm.borrow().get_pc(), pc_before_step);
+    }
+
+    #[test]
+    fn run_for_stops_at_end_of_program() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        let end = runner.initialize().unwrap();
+
+        assert_eq!(runner.run_for(3).unwrap(), 3);
+        let steps = runner.run_for(usize::MAX).unwrap();
+        assert!(steps > 0);
+        assert_eq!(PyRelocatable::from(runner.pyvm.vm.borrow().get_pc()), end);
+        assert_eq!(runner.run_for(10).unwrap(), 0);
+    }
+
+    #[test]
+    fn run_until_breakpoint_by_label() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+
+        let breakpoint = Python::with_gil(|py| {
+            runner
+                .add_breakpoint(py.eval("'fib'", None, None).unwrap())
+                .unwrap()
+        });
+        assert_eq!(runner.breakpoints(), vec![breakpoint.clone()]);
+
+        // fib is called once from main and then recursively 10 more times
+        for _ in 0..11 {
+            assert_eq!(
+                runner.run_until_breakpoint(None).unwrap(),
+                Some(breakpoint.clone())
+            );
+            assert_eq!(
+                PyRelocatable::from(runner.pyvm.vm.borrow().get_pc()),
+                breakpoint
+            );
+        }
+        assert_eq!(runner.run_until_breakpoint(None).unwrap(), None);
+    }
+
+    #[test]
+    fn remove_breakpoint() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+
+        Python::with_gil(|py| {
+            let label = py.eval("'fib_body'", None, None).unwrap();
+            runner.add_breakpoint(label).unwrap();
+            assert!(runner.remove_breakpoint(label).unwrap());
+            assert!(!runner.remove_breakpoint(label).unwrap());
+            assert!(runner
+                .add_breakpoint(py.eval("[]", None, None).unwrap())
+                .is_err());
+        });
+        assert_eq!(runner.run_until_breakpoint(None).unwrap(), None);
+    }
+
+    #[test]
+    fn run_until_breakpoint_reports_the_step_limit() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+
+        Python::with_gil(|py| {
+            runner
+                .add_breakpoint(py.eval("'fib'", None, None).unwrap())
+                .unwrap();
+        });
+        let result = runner.run_until_breakpoint(Some(1));
+        assert!(result.is_err());
+        assert!(format!("{:?}", result).contains("No breakpoint was reached in 1 steps."));
+    }
+
+    #[test]
+    fn breakpoint_at_the_starting_pc_is_not_hit() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+
+        // main is only ever at the pc the run starts from
+        Python::with_gil(|py| {
+            runner
+                .add_breakpoint(py.eval("'main'", None, None).unwrap())
+                .unwrap();
+        });
+        assert_eq!(runner.run_until_breakpoint(None).unwrap(), None);
+    }
+
+    #[test]
+    fn step_hooks_are_called_for_every_step() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+
+        Python::with_gil(|py| {
+            let pcs = pyo3::types::PyList::empty(py);
+            let globals = pyo3::types::PyDict::new(py);
+            globals.set_item("pcs", pcs).unwrap();
+            let hook = py
+                .eval("lambda ctx, inst: pcs.append(ctx.pc)", Some(globals), None)
+                .unwrap();
+            runner.add_step_hook(py, hook.to_object(py), false).unwrap();
+            assert!(runner.add_step_hook(py, 1.to_object(py), true).is_err());
+
+            assert_eq!(runner.run_for(5).unwrap(), 5);
+            assert_eq!(pcs.len(), 5);
+
+            runner.clear_step_hooks();
+            runner.step().unwrap();
+            assert_eq!(pcs.len(), 5);
+        });
+    }
+
+    fn hint_print_vars_code(program: &str) -> String {
+        let program: serde_json::Value = serde_json::from_str(program).unwrap();
+        let hints = program["hints"].as_object().unwrap();
+        hints.values().next().unwrap()[0]["code"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn registered_python_hint_replaces_hint_code() {
+        let path = String::from("cairo_programs/hint_print_vars.json");
+        let program = fs::read_to_string(path).unwrap();
+        let code = hint_print_vars_code(&program);
+        let mut runner =
+            PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
+
+        Python::with_gil(|py| {
+            let hint = py
+                .eval(
+                    "lambda vm, ids, memory, scopes: scopes.update(c=ids.a + ids.b, keys=list(scopes))",
+                    None,
+                    None,
+                )
+                .unwrap();
+            runner
+                .register_hint(py, code.clone(), hint.to_object(py))
+                .unwrap();
+            assert!(runner.register_hint(py, code, 1.to_object(py)).is_err());
+
+            runner
+                .cairo_run_py(false, None, None, None, None, None, false, false)
+                .unwrap();
+            let c = runner.inner.exec_scopes.get_any_boxed_ref("c").unwrap();
+            let c: u32 = c.downcast_ref::<PyObject>().unwrap().extract(py).unwrap();
+            assert_eq!(c, 3);
+            // Only the scope's variables are given, not the hint's globals
+            let keys = runner.inner.exec_scopes.get_any_boxed_ref("keys").unwrap();
+            let keys: Vec<String> = keys
+                .downcast_ref::<PyObject>()
+                .unwrap()
+                .extract(py)
+                .unwrap();
+            assert!(!keys.iter().any(|key| key == "memory" || key == "ids"));
+        });
+    }
+
+    #[test]
+    fn rust_hint_replaces_hint_code() {
+        let path = String::from("cairo_programs/hint_print_vars.json");
+        let program = fs::read_to_string(path).unwrap();
+        let code = hint_print_vars_code(&program);
+        let mut runner =
+            PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
+
+        let hint_func = HintFunc(Box::new(|_vm, exec_scopes, ids_data, _, _| {
+            exec_scopes.insert_value("n_ids", ids_data.len());
+            Ok(())
+        }));
+        runner.add_hint(code, Rc::new(hint_func));
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+        assert_eq!(runner.inner.exec_scopes.get::<usize>("n_ids").unwrap(), 2);
+    }
+
+    #[test]
+    fn profile_fibonacci() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        assert!(runner.get_profile().is_err());
+
+        runner.enable_profiler();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+
+        let profile = runner.get_profile().unwrap();
+        let functions = profile.functions();
+        let (main_inclusive, main_exclusive) = functions["__main__.main"];
+        let (fib_inclusive, fib_exclusive) = functions["__main__.fib"];
+        assert_eq!(main_inclusive, profile.total_steps());
+        assert_eq!(fib_inclusive, fib_exclusive);
+        assert_eq!(main_exclusive + fib_exclusive, profile.total_steps());
+
+        let call_edges = profile.call_edges();
+        assert_eq!(
+            call_edges[&("__main__.main".to_string(), "__main__.fib".to_string())],
+            (1, fib_inclusive)
+        );
+        assert_eq!(
+            call_edges[&("__main__.fib".to_string(), "__main__.fib".to_string())].0,
+            10
+        );
+        assert!(profile.to_folded().starts_with("__main__.main "));
+
+        runner.disable_profiler();
+        assert!(runner.get_profile().is_err());
+    }
+
+    #[test]
+    fn coverage_of_executed_hints() {
+        let path = String::from("cairo_programs/assert_not_zero.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        assert!(runner.get_coverage().is_err());
+
+        runner.enable_coverage();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+
+        let coverage = runner.get_coverage().unwrap();
+        let hints = coverage.hints();
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints.values().next(), Some(&4));
+
+        let lcov = coverage.to_lcov();
+        assert!(lcov.contains("assert_not_zero.cairo"));
+        assert!(lcov.contains("FNDA:4,__main__.assert_not_zero\n"));
+        assert!(lcov.contains("FNDA:1,__main__.main\n"));
+
+        let lcov_path = temp_dir().join("assert_not_zero.lcov");
+        let lcov_path = lcov_path.to_str().unwrap();
+        coverage.write_lcov(lcov_path).unwrap();
+        assert_eq!(fs::read_to_string(lcov_path).unwrap(), lcov);
+        _ = fs::remove_file(lcov_path);
+    }
+
+    #[test]
+    fn runners_share_a_program() {
+        let program = PyProgram::from_file(
+            PathBuf::from("cairo_programs/not_main.json"),
+            Some("main".to_string()),
+        )
+        .unwrap();
+
+        let mut runner =
+            PyCairoRunner::from_program(&program, None, Some("plain".to_string()), false).unwrap();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+
+        let mut other_runner = PyCairoRunner::from_program(
+            &program,
+            Some("not_main"),
+            Some("plain".to_string()),
+            false,
+        )
+        .unwrap();
+        other_runner
+            .cairo_run_py(false, None, None, None, None, None, false, false)
+            .unwrap();
+
+        assert!(Rc::ptr_eq(&runner.identifiers, &other_runner.identifiers));
+        assert!(PyCairoRunner::from_program(&program, Some("missing"), None, false).is_err());
+    }
+
+    #[test]
+    fn restore_checkpoint_rolls_back_registers_and_memory() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+        runner.run_for(3).unwrap();
+
+        Python::with_gil(|py| {
+            let checkpoint = runner.checkpoint(py).unwrap();
+            let ap = runner.get_ap().unwrap();
+            assert!(runner.get(py, &ap).is_none());
+
+            runner.run_for(10).unwrap();
+            assert!(runner.get(py, &ap).is_some());
+
+            runner.restore(py, &checkpoint).unwrap();
+            assert_eq!(runner.get_ap().unwrap(), ap);
+            assert_eq!(
+                PyRelocatable::from(runner.pyvm.vm.borrow().get_pc()),
+                checkpoint.pc()
+            );
+            assert!(runner.get(py, &ap).is_none());
+
+            // The run can be resumed from the restored state
+            let end = PyRelocatable::from(runner.end_pc.unwrap());
+            runner.run_until_pc(&end, Some(1000)).unwrap();
+        });
+    }
+
+    #[test]
+    fn restore_checkpoint_rolls_back_scope_variables() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+
+        Python::with_gil(|py| {
+            let values = pyo3::types::PyList::new(py, [1, 2]);
+            runner
+                .inner
+                .exec_scopes
+                .assign_or_update_variable("values", any_box!(values.to_object(py)));
+            let checkpoint = runner.checkpoint(py).unwrap();
+
+            values.append(3).unwrap();
+            runner
+                .inner
+                .exec_scopes
+                .assign_or_update_variable("other", any_box!(0.to_object(py)));
+
+            runner.restore(py, &checkpoint).unwrap();
+            let restored = runner
+                .inner
+                .exec_scopes
+                .get_any_boxed_ref("values")
+                .unwrap()
+                .downcast_ref::<PyObject>()
+                .unwrap();
+            assert_eq!(restored.as_ref(py).len().unwrap(), 2);
+            assert!(runner.inner.exec_scopes.get_any_boxed_ref("other").is_err());
+        });
+    }
+
+    #[test]
+    fn restore_checkpoint_rolls_back_trace_and_steps() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program.clone(),
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        let mut other_runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+        other_runner.initialize().unwrap();
+        runner.run_for(3).unwrap();
+
+        Python::with_gil(|py| {
+            let checkpoint = runner.checkpoint(py).unwrap();
+            runner.run_for(10).unwrap();
+            runner.restore(py, &checkpoint).unwrap();
+            assert_eq!(runner.get_execution_resources().unwrap().n_steps(), 3);
+
+            // Finishing the run gives the same trace as an uninterrupted one
+            finish_run(&mut runner);
+            finish_run(&mut other_runner);
+            assert_eq!(
+                runner.get_relocated_trace().unwrap(),
+                other_runner.get_relocated_trace().unwrap()
+            );
+            let resources = runner.get_execution_resources().unwrap();
+            let other_resources = other_runner.get_execution_resources().unwrap();
+            assert_eq!(resources.n_steps(), other_resources.n_steps());
+            // The accesses before the checkpoint are still counted
+            assert_eq!(resources.n_memory_holes(), other_resources.n_memory_holes());
+        });
+    }
+
+    #[test]
+    fn restore_checkpoint_runs_each_branch_to_completion() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+        runner.run_for(5).unwrap();
+
+        Python::with_gil(|py| {
+            let checkpoint = runner.checkpoint(py).unwrap();
+            let run_branch = |runner: &mut PyCairoRunner| {
+                runner.restore(py, &checkpoint).unwrap();
+                finish_run(runner);
+                let resources = runner.get_execution_resources().unwrap();
+                (
+                    runner.get_relocated_trace().unwrap(),
+                    runner.relocated_memory().unwrap().clone(),
+                    resources.n_steps(),
+                    resources.n_memory_holes(),
+                )
+            };
+            let first = run_branch(&mut runner);
+            // The run ended and its memory and trace were relocated in between
+            let second = run_branch(&mut runner);
+            assert_eq!(first, second);
+        });
+    }
+
+    /// Finishes a run started with `initialize`, relocating its trace and memory.
+    fn finish_run(runner: &mut PyCairoRunner) {
+        let end = PyRelocatable::from(runner.end_pc.unwrap());
+        runner.run_until_pc(&end, None).unwrap();
+        runner.end_run(false).unwrap();
+        runner.relocate(true).unwrap();
+    }
+
+    #[test]
+    fn checkpoint_after_the_run_ends_fails() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner
+            .cairo_run_py(false, None, None, None, None, None, true, false)
+            .unwrap();
+
+        Python::with_gil(|py| assert!(runner.checkpoint(py).is_err()));
+    }
+
+    #[test]
+    fn restore_checkpoint_copies_dict_managers() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+
+        Python::with_gil(|py| {
+            let dict_manager = Rc::new(RefCell::new(DictManager::new()));
+            runner
+                .inner
+                .exec_scopes
+                .assign_or_update_variable("dict_manager", any_box!(Rc::clone(&dict_manager)));
+            runner.inner.exec_scopes.enter_scope(HashMap::from([(
+                "dict_manager".to_string(),
+                any_box!(Rc::clone(&dict_manager)),
+            )]));
+            let checkpoint = runner.checkpoint(py).unwrap();
+
+            dict_manager
+                .borrow_mut()
+                .trackers
+                .insert(1, DictTracker::new_empty(Relocatable::from((1, 0))));
+
+            runner.restore(py, &checkpoint).unwrap();
+            let restored: Vec<_> = runner
+                .inner
+                .exec_scopes
+                .data
+                .iter()
+                .map(|scope| {
+                    scope["dict_manager"]
+                        .downcast_ref::<Rc<RefCell<DictManager>>>()
+                        .unwrap()
+                })
+                .collect();
+            assert!(restored[0].borrow().trackers.is_empty());
+            // Scopes still share the same dict manager
+            assert!(Rc::ptr_eq(restored[0], restored[1]));
+            assert!(!Rc::ptr_eq(restored[0], &dict_manager));
+        });
+    }
+
+    #[test]
+    fn restore_checkpoint_from_another_runner_fails() {
+        let path = String::from("cairo_programs/fibonacci.json");
+        let program = fs::read_to_string(path).unwrap();
+        let mut runner = PyCairoRunner::new(
+            program.clone(),
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        let mut other_runner = PyCairoRunner::new(
+            program,
+            Some("main".to_string()),
+            Some("small".to_string()),
+            false,
+        )
+        .unwrap();
+        runner.initialize().unwrap();
+        other_runner.initialize().unwrap();
+
+        Python::with_gil(|py| {
+            let checkpoint = runner.checkpoint(py).unwrap();
+            assert!(other_runner.restore(py, &checkpoint).is_err());
+        });
+    }
+
     #[test]
     fn mark_as_accessed_run_not_finished() {
         let path = String::from("cairo_programs/fibonacci.json");
@@ -2545,7 +5148,7 @@ mod test {
         .unwrap();
 
         runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .expect("Call to PyCairoRunner::cairo_run_py() failed.");
 
         Python::with_gil(|py| {
@@ -2578,7 +5181,7 @@ mod test {
         .unwrap();
 
         runner
-            .cairo_run_py(true, None, None, None, None, None)
+            .cairo_run_py(true, None, None, None, None, None, false, false)
             .expect("Call to PyCairoRunner::cairo_run_py() failed.");
 
         Python::with_gil(|py| {
@@ -2601,7 +5204,7 @@ mod test {
         .unwrap();
 
         assert!(runner
-            .cairo_run_py(false, None, None, None, None, None)
+            .cairo_run_py(false, None, None, None, None, None, false, false)
             .is_ok());
     }
 }
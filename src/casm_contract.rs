@@ -0,0 +1,303 @@
+use crate::{builtin_runner::builtin_name_matches, utils::to_py_error};
+use cairo_lang_starknet::casm_contract_class::{CasmContractClass, CasmContractEntryPoint};
+use cairo_vm::{
+    felt::Felt252,
+    hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor,
+    serde::deserialize_program::BuiltinName,
+    types::{
+        program::Program,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{runners::cairo_runner::CairoRunner, vm_core::VirtualMachine},
+};
+use num_bigint::BigUint;
+use pyo3::{exceptions::PyTypeError, exceptions::PyValueError, prelude::*};
+use serde_json::Value;
+
+// The compiled contract reads the builtin costs through a pointer stored
+// right after its bytecode, following a final `ret`. They are the gas costs of
+// the pedersen, bitwise, ec_op, poseidon and segment_arena builtins.
+const BUILTIN_COSTS_LEN: usize = 5;
+const RET_INSTRUCTION: u64 = 0x208b7fff7fff7ffe;
+const PROGRAM_EXTRA_DATA_LEN: usize = 2;
+// gas, syscall_ptr, panic flag, retdata start and retdata end
+const RETURN_VALUES_LEN: usize = 5;
+
+/// The entry point of a compiled Cairo 1 (CASM) contract class that a runner
+/// was created for.
+#[derive(Clone)]
+pub(crate) struct CasmEntrypoint {
+    pub(crate) entrypoint: CasmContractEntryPoint,
+    bytecode_len: usize,
+}
+
+impl CasmEntrypoint {
+    /// Loads a CASM contract class given its JSON as either str or bytes, and
+    /// looks up the entry point with the given selector. Returns the class'
+    /// program and the hint processor that runs its hints too.
+    pub(crate) fn load(
+        contract_class: &PyAny,
+        entrypoint_selector: &BigUint,
+    ) -> PyResult<(Program, Cairo1HintProcessor, Self)> {
+        let contract_class: CasmContractClass = if let Ok(json) = contract_class.extract::<&str>() {
+            serde_json::from_str(json).map_err(to_py_error)?
+        } else if let Ok(json) = contract_class.extract::<&[u8]>() {
+            serde_json::from_slice(json).map_err(to_py_error)?
+        } else {
+            return Err(PyTypeError::new_err("contract_class must be str or bytes"));
+        };
+
+        let entry_points = &contract_class.entry_points_by_type;
+        let entrypoint = entry_points
+            .external
+            .iter()
+            .chain(entry_points.l1_handler.iter())
+            .chain(entry_points.constructor.iter())
+            .find(|entrypoint| &entrypoint.selector == entrypoint_selector)
+            .cloned()
+            .ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "Entry point with selector {entrypoint_selector:#x} not found"
+                ))
+            })?;
+        let hint_processor = Cairo1HintProcessor::new(&contract_class.hints);
+        let bytecode_len = contract_class.bytecode.len();
+        let program = Program::try_from(contract_class).map_err(to_py_error)?;
+
+        Ok((
+            program,
+            hint_processor,
+            CasmEntrypoint {
+                entrypoint,
+                bytecode_len,
+            },
+        ))
+    }
+
+    /// The builtins the entry point expects, in order.
+    pub(crate) fn builtins(&self) -> PyResult<Vec<BuiltinName>> {
+        self.entrypoint
+            .builtins
+            .iter()
+            .map(|name| serde_json::from_value(Value::String(name.clone())).map_err(to_py_error))
+            .collect()
+    }
+
+    /// The size of the program segment, including the data written after
+    /// the bytecode.
+    pub(crate) fn program_segment_size(&self) -> usize {
+        self.bytecode_len + PROGRAM_EXTRA_DATA_LEN
+    }
+
+    /// Writes the data the entry point reads besides its arguments, and returns
+    /// its stack: the builtins it expects, the gas, the syscall pointer and the
+    /// calldata as a `Span<felt252>`. The gas defaults to `usize::MAX` and the
+    /// builtin costs to zero, so that gas never runs out.
+    pub(crate) fn initial_stack(
+        &self,
+        vm: &mut VirtualMachine,
+        calldata: Vec<BigUint>,
+        initial_gas: Option<usize>,
+        builtin_costs: Option<Vec<BigUint>>,
+    ) -> PyResult<Vec<MaybeRelocatable>> {
+        let builtin_costs = match builtin_costs {
+            Some(costs) if costs.len() != BUILTIN_COSTS_LEN => {
+                return Err(PyValueError::new_err(format!(
+                    "Expected {BUILTIN_COSTS_LEN} builtin costs, got {}",
+                    costs.len()
+                )))
+            }
+            Some(costs) => costs
+                .into_iter()
+                .map(|cost| MaybeRelocatable::Int(cost.into()))
+                .collect(),
+            None => vec![MaybeRelocatable::from(Felt252::new(0)); BUILTIN_COSTS_LEN],
+        };
+
+        let mut stack = Vec::new();
+        for name in self.entrypoint.builtins.iter() {
+            let builtin = vm
+                .get_builtin_runners()
+                .iter()
+                .find(|builtin| builtin_name_matches(builtin, name))
+                .ok_or_else(|| PyValueError::new_err(format!("{name} builtin not present")))?;
+            stack.extend(builtin.initial_stack());
+        }
+        stack.push(Felt252::new(initial_gas.unwrap_or(usize::MAX)).into());
+        stack.push(vm.add_memory_segment().into());
+
+        let builtin_costs_ptr = vm.add_memory_segment();
+        vm.load_data(builtin_costs_ptr, &builtin_costs)
+            .map_err(to_py_error)?;
+        // The program is always loaded in the first segment
+        vm.load_data(
+            Relocatable::from((0, self.bytecode_len)),
+            &vec![
+                MaybeRelocatable::from(Felt252::new(RET_INSTRUCTION)),
+                MaybeRelocatable::from(builtin_costs_ptr),
+            ],
+        )
+        .map_err(to_py_error)?;
+
+        let calldata_start = vm.add_memory_segment();
+        let calldata_end = vm
+            .load_data(
+                calldata_start,
+                &calldata
+                    .into_iter()
+                    .map(|value| MaybeRelocatable::Int(value.into()))
+                    .collect::<Vec<_>>(),
+            )
+            .map_err(to_py_error)?;
+        stack.push(calldata_start.into());
+        stack.push(calldata_end.into());
+        Ok(stack)
+    }
+
+    /// Checks the stop pointers of the returned builtins, which come right
+    /// before the other return values, and decodes the returned
+    /// `Span<felt252>`. Panics are raised as errors carrying the panic data.
+    pub(crate) fn result(
+        &self,
+        runner: &CairoRunner,
+        vm: &mut VirtualMachine,
+    ) -> PyResult<Vec<BigUint>> {
+        let builtins_end = (vm.get_ap() - RETURN_VALUES_LEN).map_err(to_py_error)?;
+        runner
+            .get_builtins_final_stack(vm, builtins_end)
+            .map_err(to_py_error)?;
+
+        let return_values = vm
+            .get_return_values(RETURN_VALUES_LEN)
+            .map_err(to_py_error)?;
+        let panicked = match &return_values[2] {
+            MaybeRelocatable::Int(flag) => flag != &Felt252::new(0),
+            _ => return Err(PyValueError::new_err("Panic flag is not an integer")),
+        };
+        let (retdata_start, retdata_end) = match (&return_values[3], &return_values[4]) {
+            (
+                MaybeRelocatable::RelocatableValue(start),
+                MaybeRelocatable::RelocatableValue(end),
+            ) => (*start, *end),
+            _ => {
+                return Err(PyValueError::new_err(
+                    "Returned Span is not a pair of pointers",
+                ))
+            }
+        };
+        let retdata = vm
+            .get_integer_range(
+                retdata_start,
+                (retdata_end - retdata_start).map_err(to_py_error)?,
+            )
+            .map_err(to_py_error)?
+            .into_iter()
+            .map(|value| value.to_biguint())
+            .collect::<Vec<_>>();
+
+        if panicked {
+            return Err(PyValueError::new_err(format!(
+                "Entry point panicked with data {retdata:?}"
+            )));
+        }
+        Ok(retdata)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cairo_runner::PyCairoRunner;
+    use num_bigint::BigUint;
+    use pyo3::prelude::*;
+
+    // Two hand-assembled entry points returning their calldata, the first one
+    // as its result and the second one as panic data:
+    //   [ap] = [fp - 6], ap++   (gas)
+    //   [ap] = [fp - 5], ap++   (syscall_ptr)
+    //   [ap] = <flag>, ap++
+    //   [ap] = [fp - 4], ap++   (calldata start)
+    //   [ap] = [fp - 3], ap++   (calldata end)
+    //   ret
+    const ECHO_CONTRACT: &str = r#"{
+        "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+        "compiler_version": "1.0.0",
+        "bytecode": [
+            "0x480a7ffa7fff8000", "0x480a7ffb7fff8000", "0x480680017fff8000", "0x0",
+            "0x480a7ffc7fff8000", "0x480a7ffd7fff8000", "0x208b7fff7fff7ffe",
+            "0x480a7ffa7fff8000", "0x480a7ffb7fff8000", "0x480680017fff8000", "0x1",
+            "0x480a7ffc7fff8000", "0x480a7ffd7fff8000", "0x208b7fff7fff7ffe"
+        ],
+        "hints": [],
+        "entry_points_by_type": {
+            "EXTERNAL": [
+                {"selector": "0x1", "offset": 0, "builtins": []},
+                {"selector": "0x2", "offset": 7, "builtins": []}
+            ],
+            "L1_HANDLER": [],
+            "CONSTRUCTOR": []
+        }
+    }"#;
+
+    fn load(selector: u32) -> PyResult<PyCairoRunner> {
+        Python::with_gil(|py| {
+            PyCairoRunner::from_casm_contract(
+                ECHO_CONTRACT.to_object(py).as_ref(py),
+                BigUint::from(selector),
+                None,
+            )
+        })
+    }
+
+    #[test]
+    fn run_entrypoint_returns_span() {
+        let calldata = vec![BigUint::from(1_u32), BigUint::from(2_u32)];
+        let mut runner = load(1).unwrap();
+        assert_eq!(
+            runner
+                .run_casm_entrypoint(calldata.clone(), None, None)
+                .unwrap(),
+            calldata
+        );
+
+        let mut runner = load(1).unwrap();
+        assert_eq!(
+            runner
+                .run_casm_entrypoint(vec![], Some(1000), Some(vec![BigUint::from(1_u32); 5]))
+                .unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn run_entrypoint_with_wrong_builtin_costs() {
+        let mut runner = load(1).unwrap();
+        let err = runner
+            .run_casm_entrypoint(vec![], None, Some(vec![BigUint::from(1_u32)]))
+            .unwrap_err();
+        assert!(err.to_string().contains("Expected 5 builtin costs, got 1"));
+    }
+
+    #[test]
+    fn run_panicking_entrypoint() {
+        let mut runner = load(2).unwrap();
+        let err = runner
+            .run_casm_entrypoint(vec![BigUint::from(7_u32)], None, None)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Entry point panicked with data [7]"));
+    }
+
+    #[test]
+    fn unknown_entrypoint_selector() {
+        let err = load(3).err().unwrap();
+        assert!(err.to_string().contains("selector 0x3 not found"));
+    }
+
+    #[test]
+    fn run_casm_entrypoint_needs_a_contract_class() {
+        let program = std::fs::read_to_string("cairo_programs/fibonacci.json").unwrap();
+        let mut runner = PyCairoRunner::new(program, None, None, false).unwrap();
+        assert!(runner.run_casm_entrypoint(vec![], None, None).is_err());
+    }
+}
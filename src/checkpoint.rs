@@ -0,0 +1,340 @@
+use crate::{
+    any_box,
+    cairo_runner::SetupCall,
+    instruction::decode_instruction_at,
+    relocatable::PyRelocatable,
+    utils::to_py_error,
+    vm_core::{PyVM, HINT_NAMESPACE},
+};
+use cairo_vm::{
+    felt::Felt252,
+    hint_processor::builtin_hint_processor::dict_manager::DictManager,
+    types::{
+        instruction::{Op1Addr, Register},
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        runners::{builtin_runner::BuiltinRunner, cairo_runner::CairoRunner},
+        trace::trace_entry::TraceEntry,
+        vm_core::{VirtualMachine, VirtualMachineBuilder},
+        vm_memory::memory_segments::MemorySegmentManager,
+    },
+};
+use num_bigint::{BigInt, BigUint};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+use std::{any::Any, cell::RefCell, collections::HashMap, rc::Rc};
+
+/// A snapshot of the runner, VM and hint state that a runner can be restored to.
+///
+/// Checkpoints can only be taken before the run ends and while no temporary
+/// segments are in use, with the trace enabled. Taking one rebuilds the VM
+/// from it, which empties the VM's instruction cache.
+#[pyclass(unsendable, name = "Checkpoint")]
+pub struct PyCheckpoint {
+    runner_id: usize,
+    setup: Vec<SetupCall>,
+    end_pc: Option<Relocatable>,
+    pc: Relocatable,
+    ap: Relocatable,
+    fp: Relocatable,
+    /// The (pc, ap, fp) offsets of every executed step.
+    trace: Vec<(usize, usize, usize)>,
+    memory: Vec<Vec<Option<MaybeRelocatable>>>,
+    builtin_runners: Vec<BuiltinRunner>,
+    accessed_addresses: Vec<Relocatable>,
+    signature_count: usize,
+    exec_scopes: Vec<HashMap<String, Box<dyn Any>>>,
+    hint_locals: HashMap<String, PyObject>,
+}
+
+#[pymethods]
+impl PyCheckpoint {
+    #[getter]
+    pub fn pc(&self) -> PyRelocatable {
+        self.pc.into()
+    }
+
+    #[getter]
+    pub fn ap(&self) -> PyRelocatable {
+        self.ap.into()
+    }
+
+    #[getter]
+    pub fn fp(&self) -> PyRelocatable {
+        self.fp.into()
+    }
+}
+
+impl PyCheckpoint {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        py: Python,
+        runner_id: usize,
+        setup: &[SetupCall],
+        end_pc: Option<Relocatable>,
+        runner: &mut CairoRunner,
+        pyvm: &mut PyVM,
+        hint_locals: &HashMap<String, PyObject>,
+    ) -> PyResult<Self> {
+        let exec_scopes = ScopeCopier::new(py)?.copy_scopes(&runner.exec_scopes.data)?;
+        let mut vm = pyvm.vm.borrow_mut();
+        if vm.get_segment_used_size(0).is_some() {
+            return Err(PyValueError::new_err(
+                "Checkpoints can only be taken before the run ends",
+            ));
+        }
+        // Without temporary segments there can't be relocation rules either.
+        // The probed segment is dropped when the VM is rebuilt below.
+        if vm.add_temporary_segment().segment_index != -1 {
+            return Err(PyValueError::new_err(
+                "Checkpoints can't be taken while temporary segments are in use",
+            ));
+        }
+        let trace = read_trace(&mut vm)?;
+
+        vm.compute_segments_effective_sizes();
+        let memory = (0..)
+            .map_while(|index| vm.get_segment_used_size(index).map(|size| (index, size)))
+            .map(|(index, size)| {
+                (0..size)
+                    .map(|offset| vm.get_maybe(&Relocatable::from((index as isize, offset))))
+                    .collect()
+            })
+            .collect();
+
+        let mut checkpoint = PyCheckpoint {
+            runner_id,
+            setup: setup.to_vec(),
+            end_pc,
+            pc: vm.get_pc(),
+            ap: vm.get_ap(),
+            fp: vm.get_fp(),
+            trace,
+            memory,
+            builtin_runners: vm.get_builtin_runners().clone(),
+            accessed_addresses: Vec::new(),
+            signature_count: pyvm.signatures.len(),
+            exec_scopes,
+            hint_locals: hint_locals.clone(),
+        };
+        // Reading the trace relocated it, so the VM is rebuilt to keep running
+        *vm = checkpoint.build_vm(runner)?;
+        checkpoint.accessed_addresses = accessed_addresses(&vm, runner, &checkpoint.trace)?;
+        pyvm.accessed_addresses = checkpoint.accessed_addresses.clone();
+        Ok(checkpoint)
+    }
+
+    pub(crate) fn setup(&self) -> &[SetupCall] {
+        &self.setup
+    }
+
+    pub(crate) fn end_pc(&self) -> Option<Relocatable> {
+        self.end_pc
+    }
+
+    /// Restores the VM and hint state, returning the CairoRunner to continue
+    /// the run with. It's created by `new_runner` from the setup calls made
+    /// before the checkpoint was taken.
+    pub(crate) fn restore(
+        &self,
+        py: Python,
+        runner_id: usize,
+        new_runner: impl FnOnce(&[SetupCall]) -> PyResult<CairoRunner>,
+        pyvm: &mut PyVM,
+        hint_locals: &mut HashMap<String, PyObject>,
+    ) -> PyResult<CairoRunner> {
+        if self.runner_id != runner_id {
+            return Err(PyValueError::new_err(
+                "Checkpoint was taken from a different runner",
+            ));
+        }
+        // The signature builtin only ever adds signatures, and keeps sharing
+        // them with the builtin runners cloned into the checkpoint
+        if pyvm.signatures.len() != self.signature_count {
+            return Err(PyValueError::new_err(
+                "Cannot restore a checkpoint taken before ECDSA signatures were added",
+            ));
+        }
+
+        let mut runner = new_runner(&self.setup)?;
+        // Scope variables are copied again so the checkpoint can be restored many times
+        runner.exec_scopes.data = ScopeCopier::new(py)?.copy_scopes(&self.exec_scopes)?;
+        *hint_locals = self.hint_locals.clone();
+
+        *pyvm.vm.borrow_mut() = self.build_vm(&mut runner)?;
+        pyvm.accessed_addresses = self.accessed_addresses.clone();
+        Ok(runner)
+    }
+
+    /// Builds a VM in the checkpoint's state. The trace and step counter can
+    /// only be set when building one. Between steps the VM never has to skip
+    /// the next instruction and its run hasn't finished, as the builder
+    /// leaves them.
+    fn build_vm(&self, runner: &mut CairoRunner) -> PyResult<VirtualMachine> {
+        let mut segments = MemorySegmentManager::new();
+        for cells in &self.memory {
+            let base = segments.add();
+            let mut offset: usize = 0;
+            for data in cells.split(Option::is_none) {
+                let data = data.iter().flatten().cloned().collect();
+                segments
+                    .load_data((base + offset).map_err(to_py_error)?, &data)
+                    .map_err(to_py_error)?;
+                offset += data.len() + 1;
+            }
+        }
+
+        let mut vm = VirtualMachineBuilder::default()
+            .segments(segments)
+            .builtin_runners(self.builtin_runners.clone())
+            .trace(Some(
+                self.trace
+                    .iter()
+                    .map(|&(pc, ap, fp)| TraceEntry { pc, ap, fp })
+                    .collect(),
+            ))
+            .current_step(self.trace.len())
+            .build();
+        // Adds the builtins' validation rules, if the runner had added them
+        if runner.get_initial_fp().is_some() {
+            runner.initialize_vm(&mut vm).map_err(to_py_error)?;
+        }
+        vm.set_pc(self.pc);
+        vm.set_ap(self.ap.offset);
+        vm.set_fp(self.fp.offset);
+        Ok(vm)
+    }
+}
+
+/// Reads the VM's trace, which it only exposes once relocated. Relocating it
+/// with the execution segment at 0 only adds 1 to the pcs, so the VM has to be
+/// rebuilt afterwards.
+fn read_trace(vm: &mut VirtualMachine) -> PyResult<Vec<(usize, usize, usize)>> {
+    vm.relocate_trace(&[0, 0]).map_err(to_py_error)?;
+    let trace = vm.get_relocated_trace().map_err(|_| {
+        PyValueError::new_err("Checkpoints can only be taken with the trace enabled")
+    })?;
+    Ok(trace
+        .iter()
+        .map(|entry| (entry.pc - 1, entry.ap, entry.fp))
+        .collect())
+}
+
+/// The addresses the executed steps accessed, found again from the trace as
+/// the VM keeps them private, and those of the program loaded by the runner.
+/// The VM only lets mark them as accessed once the run finishes.
+fn accessed_addresses(
+    vm: &VirtualMachine,
+    runner: &CairoRunner,
+    trace: &[(usize, usize, usize)],
+) -> PyResult<Vec<Relocatable>> {
+    let mut addresses = Vec::with_capacity(3 * trace.len());
+    if let (Some(base), Some(_)) = (runner.program_base, runner.get_initial_fp()) {
+        let size = runner.get_program().data_len();
+        addresses.extend(
+            (0..size).map(|offset| Relocatable::from((base.segment_index, base.offset + offset))),
+        );
+    }
+
+    let pc_segment = vm.get_pc().segment_index;
+    for &(pc, ap, fp) in trace {
+        let pc = Relocatable::from((pc_segment, pc));
+        let register = |register: &Register| match register {
+            Register::AP => Relocatable::from((1, ap)),
+            Register::FP => Relocatable::from((1, fp)),
+        };
+        let instruction = decode_instruction_at(vm, pc)?;
+        let dst =
+            (register(&instruction.dst_register) + instruction.off0 as i32).map_err(to_py_error)?;
+        let op0 =
+            (register(&instruction.op0_register) + instruction.off1 as i32).map_err(to_py_error)?;
+        let op1_base = match instruction.op1_addr {
+            Op1Addr::Imm => pc,
+            Op1Addr::AP => register(&Register::AP),
+            Op1Addr::FP => register(&Register::FP),
+            Op1Addr::Op0 => vm.get_relocatable(op0).map_err(to_py_error)?,
+        };
+        let op1 = (op1_base + instruction.off2 as i32).map_err(to_py_error)?;
+        addresses.extend([dst, op0, op1]);
+    }
+    Ok(addresses)
+}
+
+/// Copies execution scopes, keeping the Python objects and dict managers that
+/// several variables share shared in the copy.
+struct ScopeCopier<'py> {
+    py: Python<'py>,
+    deepcopy: &'py PyAny,
+    memo: &'py PyDict,
+    dict_managers: HashMap<*const RefCell<DictManager>, Rc<RefCell<DictManager>>>,
+}
+
+impl<'py> ScopeCopier<'py> {
+    fn new(py: Python<'py>) -> PyResult<Self> {
+        Ok(ScopeCopier {
+            py,
+            deepcopy: py.import("copy")?.getattr("deepcopy")?,
+            memo: PyDict::new(py),
+            dict_managers: HashMap::new(),
+        })
+    }
+
+    fn copy_scopes(
+        &mut self,
+        scopes: &[HashMap<String, Box<dyn Any>>],
+    ) -> PyResult<Vec<HashMap<String, Box<dyn Any>>>> {
+        scopes
+            .iter()
+            .map(|scope| {
+                // The hints' globals are built again from the copied variables
+                scope
+                    .iter()
+                    .filter(|(name, _)| name.as_str() != HINT_NAMESPACE)
+                    .map(|(name, value)| Ok((name.clone(), self.copy(name, value.as_ref())?)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn copy(&mut self, name: &str, value: &dyn Any) -> PyResult<Box<dyn Any>> {
+        if let Some(pyobj) = value.downcast_ref::<PyObject>() {
+            let copy = self.deepcopy.call1((pyobj, self.memo))?;
+            return Ok(any_box!(copy.to_object(self.py)));
+        }
+        if let Some(dict_manager) = value.downcast_ref::<Rc<RefCell<DictManager>>>() {
+            let copy = self
+                .dict_managers
+                .entry(Rc::as_ptr(dict_manager))
+                .or_insert_with(|| Rc::new(RefCell::new(dict_manager.borrow().clone())));
+            return Ok(any_box!(Rc::clone(copy)));
+        }
+
+        // The values stored by the builtin hints
+        macro_rules! copy_as {
+            ($($ty:ty),*) => {
+                $(
+                    if let Some(value) = value.downcast_ref::<$ty>() {
+                        return Ok(any_box!(value.clone()));
+                    }
+                )*
+            };
+        }
+        copy_as!(
+            Felt252,
+            BigInt,
+            BigUint,
+            usize,
+            u64,
+            bool,
+            Vec<Felt252>,
+            Vec<BigInt>,
+            Vec<usize>,
+            Vec<u64>,
+            HashMap<Felt252, Vec<Felt252>>,
+            HashMap<Felt252, Vec<u64>>
+        );
+        Err(PyValueError::new_err(format!(
+            "Cannot checkpoint scope variable '{name}': its type can't be copied"
+        )))
+    }
+}
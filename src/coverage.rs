@@ -0,0 +1,208 @@
+use crate::{debug_info::ProgramDebugInfo, identifiers::ProgramIdentifiers};
+use pyo3::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    fs,
+};
+
+/// Counts how many times every pc offset and every hint was executed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CoverageTracker {
+    pcs: HashMap<usize, u64>,
+    hints: HashMap<(usize, usize), u64>,
+}
+
+impl CoverageTracker {
+    pub fn record_pc(&mut self, pc: usize) {
+        *self.pcs.entry(pc).or_default() += 1;
+    }
+
+    pub fn record_hint(&mut self, pc: usize, hint_index: usize) {
+        *self.hints.entry((pc, hint_index)).or_default() += 1;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FileCoverage {
+    lines: BTreeMap<u32, u64>,
+    functions: BTreeMap<String, (u32, u64)>,
+}
+
+/// Source-level coverage of a run, built by mapping the executed pcs and
+/// hints through the program's debug info.
+#[pyclass(name = "Coverage")]
+#[derive(Debug, Clone)]
+pub struct PyCoverage {
+    pcs: HashMap<usize, u64>,
+    hints: HashMap<(usize, usize), u64>,
+    files: BTreeMap<String, FileCoverage>,
+}
+
+impl PyCoverage {
+    pub(crate) fn new(
+        tracker: &CoverageTracker,
+        debug_info: &ProgramDebugInfo,
+        identifiers: &ProgramIdentifiers,
+    ) -> Self {
+        let mut files = BTreeMap::<String, FileCoverage>::new();
+        let mut hints = HashMap::new();
+
+        for (pc, location) in debug_info.instruction_locations() {
+            let count = tracker.pcs.get(&pc).copied().unwrap_or_default();
+            let file = files
+                .entry(location.inst.input_file.filename.clone())
+                .or_default();
+            // A line was run as many times as its most executed instruction
+            let line_count = file.lines.entry(location.inst.start_line).or_default();
+            *line_count = (*line_count).max(count);
+
+            for (hint_index, hint) in location.hints.iter().enumerate() {
+                let count = tracker
+                    .hints
+                    .get(&(pc, hint_index))
+                    .copied()
+                    .unwrap_or_default();
+                hints.insert((pc, hint_index), count);
+
+                let file = files
+                    .entry(hint.location.input_file.filename.clone())
+                    .or_default();
+                for line in hint.location.start_line..=hint.location.end_line {
+                    let line_count = file.lines.entry(line).or_default();
+                    *line_count = (*line_count).max(count);
+                }
+            }
+        }
+
+        for (name, pc) in identifiers.functions() {
+            if let Some(location) = debug_info.instruction_location(pc) {
+                let count = tracker.pcs.get(&pc).copied().unwrap_or_default();
+                files
+                    .entry(location.inst.input_file.filename.clone())
+                    .or_default()
+                    .functions
+                    .insert(name.to_string(), (location.inst.start_line, count));
+            }
+        }
+
+        PyCoverage {
+            pcs: tracker.pcs.clone(),
+            hints,
+            files,
+        }
+    }
+}
+
+#[pymethods]
+impl PyCoverage {
+    /// Maps every executed pc offset to the amount of times it ran.
+    #[getter]
+    pub fn executed_pcs(&self) -> HashMap<usize, u64> {
+        self.pcs.clone()
+    }
+
+    /// Maps every (pc, hint index) pair in the program to the amount of
+    /// times the hint ran.
+    #[getter]
+    pub fn hints(&self) -> HashMap<(usize, usize), u64> {
+        self.hints.clone()
+    }
+
+    /// Maps every source file to the execution count of each of its lines
+    /// holding an instruction or a hint.
+    #[getter]
+    pub fn lines(&self) -> HashMap<String, BTreeMap<u32, u64>> {
+        self.files
+            .iter()
+            .map(|(filename, file)| (filename.clone(), file.lines.clone()))
+            .collect()
+    }
+
+    /// Returns the coverage in the lcov tracefile format.
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::from("TN:\n");
+        for (filename, file) in self.files.iter() {
+            // Writing to a String can't fail
+            let _ = writeln!(lcov, "SF:{filename}");
+            for (name, (line, _)) in file.functions.iter() {
+                let _ = writeln!(lcov, "FN:{line},{name}");
+            }
+            for (name, (_, count)) in file.functions.iter() {
+                let _ = writeln!(lcov, "FNDA:{count},{name}");
+            }
+            let _ = writeln!(lcov, "FNF:{}", file.functions.len());
+            let _ = writeln!(
+                lcov,
+                "FNH:{}",
+                file.functions
+                    .values()
+                    .filter(|(_, count)| *count > 0)
+                    .count()
+            );
+            for (line, count) in file.lines.iter() {
+                let _ = writeln!(lcov, "DA:{line},{count}");
+            }
+            let _ = writeln!(lcov, "LF:{}", file.lines.len());
+            let _ = writeln!(
+                lcov,
+                "LH:{}",
+                file.lines.values().filter(|count| **count > 0).count()
+            );
+            lcov.push_str("end_of_record\n");
+        }
+        lcov
+    }
+
+    pub fn write_lcov(&self, path: &str) -> PyResult<()> {
+        Ok(fs::write(path, self.to_lcov())?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::program::PyProgram;
+    use std::path::PathBuf;
+
+    fn load(path: &str) -> (ProgramDebugInfo, ProgramIdentifiers) {
+        let program = PyProgram::from_file(PathBuf::from(path), None).unwrap();
+        (
+            (*program.debug_info).clone(),
+            (*program.identifiers).clone(),
+        )
+    }
+
+    #[test]
+    fn unexecuted_program_has_no_hit_lines() {
+        let (debug_info, identifiers) = load("cairo_programs/fibonacci.json");
+        let coverage = PyCoverage::new(&CoverageTracker::default(), &debug_info, &identifiers);
+
+        assert!(coverage.executed_pcs().is_empty());
+        let lcov = coverage.to_lcov();
+        assert!(lcov.contains("FN:"));
+        assert!(lcov.contains("LH:0\n"));
+        assert!(lcov.contains("FNH:0\n"));
+        assert!(lcov.ends_with("end_of_record\n"));
+    }
+
+    #[test]
+    fn executed_pcs_are_mapped_to_lines() {
+        let (debug_info, identifiers) = load("cairo_programs/fibonacci.json");
+        let mut tracker = CoverageTracker::default();
+        tracker.record_pc(0);
+        tracker.record_pc(0);
+        let coverage = PyCoverage::new(&tracker, &debug_info, &identifiers);
+
+        let location = debug_info.instruction_location(0).unwrap();
+        let lines = coverage.lines();
+        assert_eq!(
+            lines[&location.inst.input_file.filename][&location.inst.start_line],
+            2
+        );
+        assert!(coverage
+            .to_lcov()
+            .contains(&format!("DA:{},2\n", location.inst.start_line)));
+        assert!(coverage.to_lcov().contains("FNDA:2,__main__.main\n"));
+    }
+}
@@ -0,0 +1,123 @@
+use pyo3::{exceptions::PyValueError, PyErr};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Deserialize)]
+struct RawDebugInfo {
+    #[serde(default)]
+    instruction_locations: HashMap<String, InstructionDebugInfo>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InputFile {
+    pub filename: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SourceLocation {
+    pub input_file: InputFile,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HintDebugInfo {
+    pub location: SourceLocation,
+    /// Lines between the opening `%{` and the hint's code.
+    #[serde(default)]
+    pub n_prefix_newlines: u32,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InstructionDebugInfo {
+    #[serde(default)]
+    pub accessible_scopes: Vec<String>,
+    pub inst: SourceLocation,
+    #[serde(default)]
+    pub hints: Vec<HintDebugInfo>,
+}
+
+/// The parts of a program's `debug_info` section that cairo-vm's `Program`
+/// doesn't keep, indexed by pc offset. Only the fields something reads are
+/// parsed: the profiler's scopes and locations, the line ranges of coverage
+/// and the hint prefix lines used in hint tracebacks.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProgramDebugInfo {
+    instruction_locations: HashMap<usize, InstructionDebugInfo>,
+}
+
+impl ProgramDebugInfo {
+    /// Takes the instruction locations from the program's raw `debug_info`.
+    pub fn new(debug_info: Option<&Value>) -> Result<Self, PyErr> {
+        let raw = debug_info
+            .filter(|debug_info| !debug_info.is_null())
+            .map(RawDebugInfo::deserialize)
+            .transpose()
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+        let mut instruction_locations = HashMap::new();
+        for (pc, location) in raw
+            .map(|debug_info| debug_info.instruction_locations)
+            .unwrap_or_default()
+        {
+            let pc = pc
+                .parse::<usize>()
+                .map_err(|_| PyValueError::new_err(format!("Invalid pc '{pc}' in debug_info")))?;
+            instruction_locations.insert(pc, location);
+        }
+
+        Ok(ProgramDebugInfo {
+            instruction_locations,
+        })
+    }
+
+    pub fn instruction_location(&self, pc: usize) -> Option<&InstructionDebugInfo> {
+        self.instruction_locations.get(&pc)
+    }
+
+    pub fn instruction_locations(&self) -> impl Iterator<Item = (usize, &InstructionDebugInfo)> {
+        self.instruction_locations
+            .iter()
+            .map(|(pc, location)| (*pc, location))
+    }
+
+    /// Returns the innermost scope of the instruction at `pc`, which is the
+    /// function it belongs to.
+    pub fn function_name(&self, pc: usize) -> Option<&str> {
+        self.instruction_locations
+            .get(&pc)
+            .and_then(|location| location.accessible_scopes.last())
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn parse_fibonacci_debug_info() {
+        let program_json = fs::read("cairo_programs/fibonacci.json").unwrap();
+        let program_json: Value = serde_json::from_slice(&program_json).unwrap();
+        let debug_info = ProgramDebugInfo::new(program_json.get("debug_info")).unwrap();
+
+        assert_eq!(debug_info.function_name(0), Some("__main__.main"));
+        let location = debug_info.instruction_location(0).unwrap();
+        assert!(location
+            .inst
+            .input_file
+            .filename
+            .ends_with("fibonacci.cairo"));
+    }
+
+    #[test]
+    fn parse_program_without_debug_info() {
+        let debug_info = ProgramDebugInfo::new(None).unwrap();
+        assert!(debug_info.instruction_location(0).is_none());
+        let debug_info = ProgramDebugInfo::new(Some(&Value::Null)).unwrap();
+        assert!(debug_info.instruction_location(0).is_none());
+        assert_eq!(debug_info.function_name(0), None);
+    }
+}
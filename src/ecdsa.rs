@@ -44,6 +44,14 @@ impl PySignature {
         }
         Ok(())
     }
+
+    pub(crate) fn signatures(
+        &self,
+    ) -> impl Iterator<Item = (Relocatable, (Felt252, Felt252))> + '_ {
+        self.signatures
+            .iter()
+            .map(|(address, pair)| (Relocatable::from(address), pair.clone()))
+    }
 }
 
 impl Default for PySignature {
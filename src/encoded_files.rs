@@ -0,0 +1,141 @@
+use num_bigint::BigUint;
+use std::io::{self, Read};
+
+const WORD_SIZE_IN_BYTES: usize = 8;
+const FIELD_SIZE_IN_BYTES: usize = 32;
+// Each trace entry is encoded as its ap, fp and pc
+const TRACE_ENTRY_SIZE: usize = 3 * WORD_SIZE_IN_BYTES;
+const MEMORY_CELL_SIZE: usize = WORD_SIZE_IN_BYTES + FIELD_SIZE_IN_BYTES;
+
+/// A relocated trace entry as (pc, ap, fp), the same order used by
+/// `CairoRunner.get_relocated_trace`.
+pub type TraceEntry = (usize, usize, usize);
+/// A relocated memory cell as (address, value).
+pub type MemoryCell = (usize, BigUint);
+
+/// Streams the fixed size entries of an encoded file, failing on a trailing
+/// partial entry.
+struct EntryReader<R, const N: usize> {
+    reader: R,
+    offset: usize,
+}
+
+impl<R: Read, const N: usize> Iterator for EntryReader<R, N> {
+    type Item = io::Result<[u8; N]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut entry = [0; N];
+        let mut read = 0;
+        while read < N {
+            match self.reader.read(&mut entry[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        let offset = self.offset;
+        self.offset += read;
+        match read {
+            0 => None,
+            read if read == N => Some(Ok(entry)),
+            read => Some(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Truncated entry at byte {offset}: expected {N} bytes, found {read}"),
+            ))),
+        }
+    }
+}
+
+/// Decodes the entries written by `write_encoded_trace` one at a time.
+pub(crate) struct TraceReader<R>(EntryReader<R, TRACE_ENTRY_SIZE>);
+
+impl<R: Read> TraceReader<R> {
+    pub fn new(reader: R) -> Self {
+        TraceReader(EntryReader { reader, offset: 0 })
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = io::Result<TraceEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.0.next()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+        let word = |index: usize| {
+            let start = index * WORD_SIZE_IN_BYTES;
+            read_word(&entry[start..start + WORD_SIZE_IN_BYTES])
+        };
+        Some(Ok((word(2), word(0), word(1))))
+    }
+}
+
+/// Decodes the cells written by `write_encoded_memory` one at a time.
+pub(crate) struct MemoryReader<R>(EntryReader<R, MEMORY_CELL_SIZE>);
+
+impl<R: Read> MemoryReader<R> {
+    pub fn new(reader: R) -> Self {
+        MemoryReader(EntryReader { reader, offset: 0 })
+    }
+}
+
+impl<R: Read> Iterator for MemoryReader<R> {
+    type Item = io::Result<MemoryCell>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cell = match self.0.next()? {
+            Ok(cell) => cell,
+            Err(err) => return Some(Err(err)),
+        };
+        let (address, value) = cell.split_at(WORD_SIZE_IN_BYTES);
+        Some(Ok((read_word(address), BigUint::from_bytes_le(value))))
+    }
+}
+
+fn read_word(bytes: &[u8]) -> usize {
+    let mut word = [0; WORD_SIZE_IN_BYTES];
+    word.copy_from_slice(bytes);
+    u64::from_le_bytes(word) as usize
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode_trace(bytes: &[u8]) -> io::Result<Vec<TraceEntry>> {
+        TraceReader::new(bytes).collect()
+    }
+
+    fn decode_memory(bytes: &[u8]) -> io::Result<Vec<MemoryCell>> {
+        MemoryReader::new(bytes).collect()
+    }
+
+    #[test]
+    fn decode_encoded_entries() {
+        let mut trace = Vec::new();
+        for word in [5_u64, 6, 1, 7, 6, 3] {
+            trace.extend(word.to_le_bytes());
+        }
+        assert_eq!(decode_trace(&trace).unwrap(), vec![(1, 5, 6), (3, 7, 6)]);
+
+        let mut memory = 2_u64.to_le_bytes().to_vec();
+        memory.extend([0x2a, 1]);
+        memory.extend([0; FIELD_SIZE_IN_BYTES - 2]);
+        assert_eq!(
+            decode_memory(&memory).unwrap(),
+            vec![(2, BigUint::from(0x012a_u32))]
+        );
+    }
+
+    #[test]
+    fn decode_truncated_entries() {
+        let err = decode_trace(&[0; TRACE_ENTRY_SIZE + 4]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("Truncated entry at byte 24"));
+        assert!(decode_memory(&[0; MEMORY_CELL_SIZE - 1]).is_err());
+        assert!(decode_trace(&[]).unwrap().is_empty());
+    }
+}
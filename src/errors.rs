@@ -0,0 +1,331 @@
+use crate::relocatable::{PyMaybeRelocatable, PyRelocatable};
+use cairo_vm::{
+    types::{errors::math_errors, relocatable::Relocatable},
+    vm::errors::{
+        cairo_run_errors::CairoRunError, hint_errors, memory_errors, runner_errors, vm_errors,
+    },
+};
+use pyo3::{create_exception, exceptions::PyValueError, prelude::*};
+use std::{any::Any, fmt::Display};
+
+// All of them subclass ValueError, which used to be raised for every VM error
+create_exception!(cairo_vm_py, VmError, PyValueError);
+create_exception!(cairo_vm_py, VirtualMachineError, VmError);
+// Not named `MemoryError`, which would shadow Python's builtin on star imports
+create_exception!(cairo_vm_py, VmMemoryError, VmError);
+create_exception!(cairo_vm_py, HintError, VmError);
+// Carries `address` and `value` when the failed operation involved a relocatable
+create_exception!(cairo_vm_py, MathError, VmError);
+create_exception!(cairo_vm_py, RunnerError, VmError);
+
+// Carry `expected` and `actual`
+create_exception!(cairo_vm_py, DiffAssertValuesError, VirtualMachineError);
+// Carries `address`, `expected` (the current value) and `actual` (the new one)
+create_exception!(cairo_vm_py, InconsistentMemoryError, VmMemoryError);
+// Carries `address`
+create_exception!(cairo_vm_py, UnknownMemoryCellError, VmMemoryError);
+// Carries `name`
+create_exception!(cairo_vm_py, UnknownIdentifierError, HintError);
+// Carries `code`
+create_exception!(cairo_vm_py, UnknownHintError, HintError);
+// Carries `value`
+create_exception!(cairo_vm_py, AssertNotZeroError, HintError);
+
+type ErrorClass = fn(String) -> PyErr;
+type Attributes = Vec<(&'static str, PyObject)>;
+
+/// Adds the VM's exception classes to the module.
+pub(crate) fn add_exceptions(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("VmError", py.get_type::<VmError>())?;
+    m.add("VirtualMachineError", py.get_type::<VirtualMachineError>())?;
+    m.add("VmMemoryError", py.get_type::<VmMemoryError>())?;
+    m.add("HintError", py.get_type::<HintError>())?;
+    m.add("MathError", py.get_type::<MathError>())?;
+    m.add("RunnerError", py.get_type::<RunnerError>())?;
+    m.add(
+        "DiffAssertValuesError",
+        py.get_type::<DiffAssertValuesError>(),
+    )?;
+    m.add(
+        "InconsistentMemoryError",
+        py.get_type::<InconsistentMemoryError>(),
+    )?;
+    m.add(
+        "UnknownMemoryCellError",
+        py.get_type::<UnknownMemoryCellError>(),
+    )?;
+    m.add(
+        "UnknownIdentifierError",
+        py.get_type::<UnknownIdentifierError>(),
+    )?;
+    m.add("UnknownHintError", py.get_type::<UnknownHintError>())?;
+    m.add("AssertNotZeroError", py.get_type::<AssertNotZeroError>())?;
+    Ok(())
+}
+
+/// Builds the exception matching one of the VM's errors, keeping its message.
+/// Errors raised while running a hint also carry its `hint_index`.
+/// Returns None for errors that don't come from the VM.
+pub(crate) fn vm_error_to_py<T: Display + 'static>(error: &T) -> Option<PyErr> {
+    let any_error: &dyn Any = error;
+    Python::with_gil(|py| {
+        let mut attributes = Vec::new();
+        let class = if let Some(error) = any_error.downcast_ref::<vm_errors::VirtualMachineError>()
+        {
+            vm_error_class(py, error, &mut attributes)
+        } else if let Some(error) = any_error.downcast_ref::<memory_errors::MemoryError>() {
+            memory_error_class(py, error, &mut attributes)
+        } else if let Some(error) = any_error.downcast_ref::<hint_errors::HintError>() {
+            hint_error_class(py, error, &mut attributes)
+        } else if let Some(error) = any_error.downcast_ref::<math_errors::MathError>() {
+            math_error_class(py, error, &mut attributes)
+        } else if any_error.is::<runner_errors::RunnerError>() {
+            RunnerError::new_err::<String>
+        } else if let Some(error) = any_error.downcast_ref::<CairoRunError>() {
+            match error {
+                CairoRunError::VirtualMachine(error) => vm_error_class(py, error, &mut attributes),
+                CairoRunError::VmException(exception) => {
+                    vm_error_class(py, &exception.inner_exc, &mut attributes)
+                }
+                CairoRunError::MemoryError(error) => memory_error_class(py, error, &mut attributes),
+                CairoRunError::Runner(_) => RunnerError::new_err::<String>,
+                _ => VmError::new_err::<String>,
+            }
+        } else {
+            return None;
+        };
+
+        let exception = class(error.to_string());
+        let value = exception.value(py);
+        for (name, attribute) in attributes {
+            if let Err(err) = value.setattr(name, attribute) {
+                return Some(err);
+            }
+        }
+        Some(exception)
+    })
+}
+
+fn vm_error_class(
+    py: Python,
+    error: &vm_errors::VirtualMachineError,
+    attributes: &mut Attributes,
+) -> ErrorClass {
+    use vm_errors::VirtualMachineError::*;
+    match error {
+        Memory(error) => memory_error_class(py, error, attributes),
+        Math(error) => math_error_class(py, error, attributes),
+        RunnerError(_) => self::RunnerError::new_err::<String>,
+        Hint(hint_error) => {
+            let (hint_index, error) = hint_error.as_ref();
+            attributes.push(("hint_index", hint_index.into_py(py)));
+            hint_error_class(py, error, attributes)
+        }
+        DiffAssertValues(values) => {
+            let (expected, actual) = values.as_ref();
+            attributes.push(("expected", maybe_relocatable_to_py(py, expected)));
+            attributes.push(("actual", maybe_relocatable_to_py(py, actual)));
+            DiffAssertValuesError::new_err::<String>
+        }
+        _ => self::VirtualMachineError::new_err::<String>,
+    }
+}
+
+fn memory_error_class(
+    py: Python,
+    error: &memory_errors::MemoryError,
+    attributes: &mut Attributes,
+) -> ErrorClass {
+    use memory_errors::MemoryError::*;
+    match error {
+        Math(error) => math_error_class(py, error, attributes),
+        InconsistentMemory(values) => {
+            let (address, expected, actual) = values.as_ref();
+            attributes.push(("address", PyRelocatable::from(*address).into_py(py)));
+            attributes.push(("expected", maybe_relocatable_to_py(py, expected)));
+            attributes.push(("actual", maybe_relocatable_to_py(py, actual)));
+            InconsistentMemoryError::new_err::<String>
+        }
+        UnknownMemoryCell(address) => {
+            attributes.push(("address", PyRelocatable::from(**address).into_py(py)));
+            UnknownMemoryCellError::new_err::<String>
+        }
+        _ => VmMemoryError::new_err::<String>,
+    }
+}
+
+fn hint_error_class(
+    py: Python,
+    error: &hint_errors::HintError,
+    attributes: &mut Attributes,
+) -> ErrorClass {
+    use hint_errors::HintError::*;
+    match error {
+        Internal(error) => vm_error_class(py, error, attributes),
+        Memory(error) => memory_error_class(py, error, attributes),
+        Math(error) => math_error_class(py, error, attributes),
+        UnknownIdentifier(name) => {
+            attributes.push(("name", name.to_string().into_py(py)));
+            UnknownIdentifierError::new_err::<String>
+        }
+        UnknownHint(code) => {
+            attributes.push(("code", code.to_string().into_py(py)));
+            UnknownHintError::new_err::<String>
+        }
+        AssertNotZero(values) => {
+            attributes.push(("value", values.0.to_biguint().into_py(py)));
+            AssertNotZeroError::new_err::<String>
+        }
+        _ => self::HintError::new_err::<String>,
+    }
+}
+
+fn math_error_class(
+    py: Python,
+    error: &math_errors::MathError,
+    attributes: &mut Attributes,
+) -> ErrorClass {
+    use math_errors::MathError::*;
+    let relocatable = |value: &Relocatable| -> PyObject { PyRelocatable::from(*value).into_py(py) };
+    let (address, value): (PyObject, PyObject) = match error {
+        RelocatableAddFelt252OffsetExceeded(values) => {
+            (relocatable(&values.0), values.1.to_biguint().into_py(py))
+        }
+        RelocatableSubNegOffset(values) | RelocatableAddUsizeOffsetExceeded(values) => {
+            (relocatable(&values.0), values.1.into_py(py))
+        }
+        RelocatableAdd(values) | RelocatableSubDiffIndex(values) => {
+            (relocatable(&values.0), relocatable(&values.1))
+        }
+        SubRelocatableFromInt(values) => {
+            (relocatable(&values.1), values.0.to_biguint().into_py(py))
+        }
+        _ => return MathError::new_err::<String>,
+    };
+    attributes.push(("address", address));
+    attributes.push(("value", value));
+    MathError::new_err::<String>
+}
+
+fn maybe_relocatable_to_py(
+    py: Python,
+    value: &cairo_vm::types::relocatable::MaybeRelocatable,
+) -> PyObject {
+    PyMaybeRelocatable::from(value).to_object(py)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::to_py_error;
+    use cairo_vm::{felt::Felt252, types::relocatable::MaybeRelocatable};
+
+    #[test]
+    fn inconsistent_memory_error() {
+        let error = memory_errors::MemoryError::InconsistentMemory(Box::new((
+            Relocatable::from((1, 2)),
+            MaybeRelocatable::from(Felt252::new(3)),
+            MaybeRelocatable::from(Relocatable::from((4, 5))),
+        )));
+        let message = error.to_string();
+        let exception = to_py_error(vm_errors::VirtualMachineError::Memory(error));
+
+        Python::with_gil(|py| {
+            assert!(exception.is_instance_of::<InconsistentMemoryError>(py));
+            assert!(exception.is_instance_of::<VmMemoryError>(py));
+            assert!(exception.is_instance_of::<VmError>(py));
+            assert!(exception.is_instance_of::<PyValueError>(py));
+            assert_eq!(exception.value(py).to_string(), message);
+
+            let value = exception.value(py);
+            let address: PyRelocatable = value.getattr("address").unwrap().extract().unwrap();
+            assert_eq!(address, PyRelocatable::from((1, 2)));
+            let expected: u32 = value.getattr("expected").unwrap().extract().unwrap();
+            assert_eq!(expected, 3);
+            let actual: PyRelocatable = value.getattr("actual").unwrap().extract().unwrap();
+            assert_eq!(actual, PyRelocatable::from((4, 5)));
+        });
+    }
+
+    #[test]
+    fn hint_errors_carry_the_hint_index() {
+        let error = vm_errors::VirtualMachineError::Hint(Box::new((
+            2,
+            hint_errors::HintError::UnknownIdentifier("x".to_string().into_boxed_str()),
+        )));
+        let exception = to_py_error(error);
+
+        Python::with_gil(|py| {
+            assert!(exception.is_instance_of::<UnknownIdentifierError>(py));
+            assert!(exception.is_instance_of::<HintError>(py));
+            let value = exception.value(py);
+            let hint_index: usize = value.getattr("hint_index").unwrap().extract().unwrap();
+            assert_eq!(hint_index, 2);
+            let name: String = value.getattr("name").unwrap().extract().unwrap();
+            assert_eq!(name, "x");
+        });
+    }
+
+    #[test]
+    fn diff_assert_values_error() {
+        let error = vm_errors::VirtualMachineError::DiffAssertValues(Box::new((
+            MaybeRelocatable::from(Felt252::new(1)),
+            MaybeRelocatable::from(Felt252::new(2)),
+        )));
+        let exception = to_py_error(error);
+
+        Python::with_gil(|py| {
+            assert!(exception.is_instance_of::<DiffAssertValuesError>(py));
+            assert!(exception.is_instance_of::<VirtualMachineError>(py));
+            let value = exception.value(py);
+            let expected: u32 = value.getattr("expected").unwrap().extract().unwrap();
+            let actual: u32 = value.getattr("actual").unwrap().extract().unwrap();
+            assert_eq!((expected, actual), (1, 2));
+        });
+    }
+
+    #[test]
+    fn other_errors_are_value_errors() {
+        let exception = to_py_error("Invalid pointer");
+        let math_exception = to_py_error(math_errors::MathError::RelocatableSubDiffIndex(
+            Box::new((Relocatable::from((0, 0)), Relocatable::from((1, 0)))),
+        ));
+
+        Python::with_gil(|py| {
+            assert!(exception.is_instance_of::<PyValueError>(py));
+            assert!(!exception.is_instance_of::<VmError>(py));
+            assert!(math_exception.is_instance_of::<MathError>(py));
+        });
+    }
+
+    #[test]
+    fn math_errors_carry_the_relocatable_operands() {
+        let error =
+            vm_errors::VirtualMachineError::Math(math_errors::MathError::RelocatableSubNegOffset(
+                Box::new((Relocatable::from((1, 2)), 3)),
+            ));
+        let exception = to_py_error(error);
+
+        Python::with_gil(|py| {
+            assert!(exception.is_instance_of::<MathError>(py));
+            let value = exception.value(py);
+            let address: PyRelocatable = value.getattr("address").unwrap().extract().unwrap();
+            assert_eq!(address, PyRelocatable::from((1, 2)));
+            let offset: usize = value.getattr("value").unwrap().extract().unwrap();
+            assert_eq!(offset, 3);
+        });
+    }
+
+    #[test]
+    fn exceptions_are_added_to_the_module() {
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "cairo_vm_py").unwrap();
+            add_exceptions(py, module).unwrap();
+            let memory_error = module.getattr("InconsistentMemoryError").unwrap();
+            assert!(memory_error.is(py.get_type::<InconsistentMemoryError>()));
+            assert!(module.getattr("MemoryError").is_err());
+            let memory_error = module.getattr("VmMemoryError").unwrap();
+            assert!(memory_error.is(py.get_type::<VmMemoryError>()));
+        });
+    }
+}
@@ -0,0 +1,259 @@
+use cairo_vm::{serde::deserialize_program::Identifier, types::program::Program};
+use num_bigint::BigUint;
+use pyo3::{exceptions::PyValueError, PyErr};
+use serde_json::Value;
+use std::collections::HashMap;
+
+const MAIN_SCOPE: &str = "__main__";
+const MAX_CLOSE_MATCHES: usize = 5;
+
+/// The identifiers of a program, together with the alias destinations and
+/// type definitions that cairo-vm's `Identifier` doesn't keep around.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProgramIdentifiers {
+    identifiers: HashMap<String, Identifier>,
+    aliases: HashMap<String, String>,
+    type_definitions: HashMap<String, String>,
+}
+
+impl ProgramIdentifiers {
+    /// Takes the aliases and type definitions from the program's raw identifiers.
+    pub fn new(program: &Program, raw_identifiers: &HashMap<String, Value>) -> Self {
+        let mut aliases = HashMap::new();
+        let mut type_definitions = HashMap::new();
+        for (path, identifier) in raw_identifiers {
+            let field = |name: &str| identifier.get(name).and_then(Value::as_str);
+            match (field("type"), field("destination"), field("cairo_type")) {
+                (Some("alias"), Some(destination), _) => {
+                    aliases.insert(path.clone(), destination.to_string());
+                }
+                (Some("type_definition"), _, Some(cairo_type)) => {
+                    type_definitions.insert(path.clone(), cairo_type.to_string());
+                }
+                _ => {}
+            }
+        }
+
+        let identifiers = program
+            .iter_identifiers()
+            .map(|(path, identifier)| (path.to_string(), identifier.clone()))
+            .collect();
+
+        ProgramIdentifiers {
+            identifiers,
+            aliases,
+            type_definitions,
+        }
+    }
+
+    /// Follows a chain of aliases until reaching a non-alias identifier name.
+    pub fn resolve_alias<'a>(&'a self, mut full_name: &'a str) -> Result<&'a str, PyErr> {
+        // An alias chain can't be longer than the amount of aliases without looping.
+        for _ in 0..=self.aliases.len() {
+            match self.aliases.get(full_name) {
+                Some(destination) => full_name = destination,
+                None => return Ok(full_name),
+            }
+        }
+        Err(PyValueError::new_err(format!(
+            "Cyclic alias found while resolving '{full_name}'"
+        )))
+    }
+
+    /// Returns the pc of a function given either its fully qualified name
+    /// (`__main__.foo`) or its short name (`foo`).
+    pub fn get_function_pc(&self, name: &str) -> Result<usize, PyErr> {
+        let mut candidates: Vec<(&str, usize)> = Vec::new();
+        for full_name in self.matching_names(name) {
+            let resolved = self.resolve_alias(full_name)?;
+            if let Some(pc) = self.identifiers.get(resolved).and_then(|id| id.pc) {
+                if !candidates.iter().any(|(_, other_pc)| *other_pc == pc) {
+                    candidates.push((resolved, pc));
+                }
+            }
+        }
+
+        match candidates.as_slice() {
+            [(_, pc)] => Ok(*pc),
+            [] => Err(PyValueError::new_err(format!(
+                "Unknown entrypoint '{name}'.{}",
+                self.close_matches_msg(name)
+            ))),
+            _ => {
+                let mut names = candidates.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                names.sort_unstable();
+                Err(PyValueError::new_err(format!(
+                    "Ambiguous entrypoint '{name}', it may refer to: {}",
+                    names.join(", ")
+                )))
+            }
+        }
+    }
+
+    /// Returns the fully qualified name and pc of every function in the program.
+    pub fn functions(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.identifiers
+            .iter()
+            .filter(|(_, identifier)| identifier.type_.as_deref() == Some("function"))
+            .filter_map(|(name, identifier)| identifier.pc.map(|pc| (name.as_str(), pc)))
+    }
+
+    /// Returns the fully qualified name of the function starting at the given pc.
+    pub fn function_name(&self, pc: usize) -> Option<&str> {
+        self.functions()
+            .find(|(_, function_pc)| *function_pc == pc)
+            .map(|(name, _)| name)
+    }
+
+    /// Returns the type a type definition (i.e. a function's `Return`) stands for.
+    pub fn type_definition(&self, full_name: &str) -> Option<&str> {
+        self.type_definitions.get(full_name).map(String::as_str)
+    }
+
+    /// Returns the value of every constant in the program.
+    pub fn constants(&self) -> HashMap<String, BigUint> {
+        self.identifiers
+            .iter()
+            .filter(|(_, identifier)| identifier.type_.as_deref() == Some("const"))
+            .filter_map(|(name, identifier)| {
+                identifier
+                    .value
+                    .as_ref()
+                    .map(|value| (name.clone(), value.to_biguint()))
+            })
+            .collect()
+    }
+
+    fn matching_names<'a>(&'a self, name: &'a str) -> Vec<&'a str> {
+        let main_name = format!("{MAIN_SCOPE}.{name}");
+        for exact in [name, main_name.as_str()] {
+            if let Some((full_name, _)) = self.identifiers.get_key_value(exact) {
+                // A struct or namespace of the same name doesn't hide the functions in others
+                let pc = self
+                    .resolve_alias(full_name)
+                    .ok()
+                    .and_then(|resolved| self.identifiers.get(resolved))
+                    .and_then(|identifier| identifier.pc);
+                if pc.is_some() {
+                    return vec![full_name.as_str()];
+                }
+            }
+        }
+
+        let suffix = format!(".{name}");
+        self.identifiers
+            .keys()
+            .filter(|full_name| full_name.ends_with(&suffix))
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn close_matches_msg(&self, name: &str) -> String {
+        let mut matches = self
+            .identifiers
+            .iter()
+            .filter(|(_, identifier)| identifier.pc.is_some())
+            .map(|(full_name, _)| {
+                let short_name = full_name.rsplit('.').next().unwrap_or(full_name);
+                let distance = levenshtein(name, short_name).min(levenshtein(name, full_name));
+                (distance, full_name.as_str())
+            })
+            .filter(|(distance, _)| *distance <= name.len() / 2 + 1)
+            .collect::<Vec<_>>();
+
+        if matches.is_empty() {
+            return String::new();
+        }
+        matches.sort_unstable();
+        let names = matches
+            .into_iter()
+            .take(MAX_CLOSE_MATCHES)
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+        format!(" Close matches: {}", names.join(", "))
+    }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b_chars = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b_chars.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut prev_diag = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let prev_row = row[j + 1];
+            row[j + 1] = if a_char == *b_char {
+                prev_diag
+            } else {
+                1 + prev_diag.min(prev_row).min(row[j])
+            };
+            prev_diag = prev_row;
+        }
+    }
+    row[b_chars.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::program::PyProgram;
+    use std::path::PathBuf;
+
+    fn load(path: &str) -> ProgramIdentifiers {
+        let program = PyProgram::from_file(PathBuf::from(path), None).unwrap();
+        (*program.identifiers).clone()
+    }
+
+    #[test]
+    fn get_function_pc_by_short_and_full_name() {
+        let identifiers = load("cairo_programs/not_main.json");
+        assert_eq!(identifiers.get_function_pc("not_main").unwrap(), 0);
+        assert_eq!(identifiers.get_function_pc("__main__.not_main").unwrap(), 0);
+        assert_eq!(identifiers.get_function_pc("main").unwrap(), 3);
+    }
+
+    #[test]
+    fn get_function_pc_follows_aliases() {
+        let identifiers = load("cairo_programs/array_sum.json");
+        assert_eq!(
+            identifiers.get_function_pc("__main__.alloc").unwrap(),
+            identifiers
+                .get_function_pc("starkware.cairo.common.alloc.alloc")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn get_function_pc_skips_exact_matches_without_pc() {
+        let identifier = |value: Value| serde_json::from_value::<Identifier>(value).unwrap();
+        let identifiers = ProgramIdentifiers {
+            identifiers: HashMap::from([
+                (
+                    "__main__.foo".to_string(),
+                    identifier(serde_json::json!({ "type": "struct" })),
+                ),
+                (
+                    "__main__.bar.foo".to_string(),
+                    identifier(serde_json::json!({ "type": "function", "pc": 4 })),
+                ),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(identifiers.get_function_pc("foo").unwrap(), 4);
+    }
+
+    #[test]
+    fn get_function_pc_unknown_name_lists_close_matches() {
+        let identifiers = load("cairo_programs/not_main.json");
+        let err = identifiers.get_function_pc("not_mian").unwrap_err();
+        assert!(err.to_string().contains("Unknown entrypoint 'not_mian'"));
+        assert!(err.to_string().contains("__main__.not_main"));
+    }
+
+    #[test]
+    fn levenshtein_distance() {
+        assert_eq!(levenshtein("main", "main"), 0);
+        assert_eq!(levenshtein("main", "mian"), 2);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+}
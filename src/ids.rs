@@ -895,10 +895,9 @@ memory[fp] = ids.ok_ref
             let py_result = py.run(code, Some(globals), None);
 
             assert!(py_result.unwrap_err().to_string().contains(
-                &PyValueError::new_err(
-                    HintError::UnknownIdentifier("bad_ref".to_string().into_boxed_str())
-                        .to_string()
-                )
+                &to_py_error(HintError::UnknownIdentifier(
+                    "bad_ref".to_string().into_boxed_str()
+                ))
                 .to_string()
             ));
 
@@ -906,10 +905,9 @@ memory[fp] = ids.ok_ref
 
             let py_result = py.run(code, Some(globals), None);
             assert!(py_result.unwrap_err().to_string().contains(
-                &PyValueError::new_err(
-                    HintError::UnknownIdentifier("none_ref".to_string().into_boxed_str())
-                        .to_string()
-                )
+                &to_py_error(HintError::UnknownIdentifier(
+                    "none_ref".to_string().into_boxed_str()
+                ))
                 .to_string()
             ));
         });
@@ -0,0 +1,229 @@
+use cairo_vm::{
+    types::{
+        instruction::{ApUpdate, FpUpdate, Instruction, Op1Addr, Opcode, PcUpdate, Register, Res},
+        relocatable::Relocatable,
+    },
+    vm::{decoding::decoder::decode_instruction, vm_core::VirtualMachine},
+};
+use num_bigint::BigUint;
+use pyo3::prelude::*;
+
+use crate::utils::to_py_error;
+
+const INVALID_INSTRUCTION_ENCODING_MSG: &str = "Instruction encoding doesn't fit in 64 bits";
+
+#[pyclass(name = "Instruction")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PyInstruction {
+    #[pyo3(get)]
+    off0: isize,
+    #[pyo3(get)]
+    off1: isize,
+    #[pyo3(get)]
+    off2: isize,
+    #[pyo3(get)]
+    imm: Option<BigUint>,
+    #[pyo3(get)]
+    dst_register: String,
+    #[pyo3(get)]
+    op0_register: String,
+    #[pyo3(get)]
+    op1_addr: String,
+    #[pyo3(get)]
+    res: String,
+    #[pyo3(get)]
+    pc_update: String,
+    #[pyo3(get)]
+    ap_update: String,
+    #[pyo3(get)]
+    fp_update: String,
+    #[pyo3(get)]
+    opcode: String,
+}
+
+/// Decodes the instruction stored at `pc`.
+pub(crate) fn decode_instruction_at(vm: &VirtualMachine, pc: Relocatable) -> PyResult<Instruction> {
+    let encoding = u64::try_from(&vm.get_integer(pc).map_err(to_py_error)?.to_biguint())
+        .map_err(|_| to_py_error(INVALID_INSTRUCTION_ENCODING_MSG))?;
+    decode_instruction(encoding).map_err(to_py_error)
+}
+
+#[pymethods]
+impl PyInstruction {
+    #[getter]
+    pub fn size(&self) -> usize {
+        match self.imm {
+            Some(_) => 2,
+            None => 1,
+        }
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "Instruction(off0={}, off1={}, off2={}, imm={:?}, dst_register={}, op0_register={}, \
+             op1_addr={}, res={}, pc_update={}, ap_update={}, fp_update={}, opcode={})",
+            self.off0,
+            self.off1,
+            self.off2,
+            self.imm,
+            self.dst_register,
+            self.op0_register,
+            self.op1_addr,
+            self.res,
+            self.pc_update,
+            self.ap_update,
+            self.fp_update,
+            self.opcode,
+        )
+    }
+}
+
+impl PyInstruction {
+    /// Builds the instruction decoded at `pc`, reading its immediate if it has one.
+    pub(crate) fn new(vm: &VirtualMachine, pc: Relocatable, instruction: &Instruction) -> Self {
+        let imm = match instruction.op1_addr {
+            Op1Addr::Imm => vm
+                .get_integer(Relocatable::from((pc.segment_index, pc.offset + 1)))
+                .ok()
+                .map(|imm| imm.to_biguint()),
+            _ => None,
+        };
+        PyInstruction {
+            off0: instruction.off0,
+            off1: instruction.off1,
+            off2: instruction.off2,
+            imm,
+            dst_register: register_name(&instruction.dst_register).to_string(),
+            op0_register: register_name(&instruction.op0_register).to_string(),
+            op1_addr: op1_addr_name(&instruction.op1_addr).to_string(),
+            res: res_name(&instruction.res).to_string(),
+            pc_update: pc_update_name(&instruction.pc_update).to_string(),
+            ap_update: ap_update_name(&instruction.ap_update).to_string(),
+            fp_update: fp_update_name(&instruction.fp_update).to_string(),
+            opcode: opcode_name(&instruction.opcode).to_string(),
+        }
+    }
+}
+
+// The names of the flags in cairo-lang's Instruction
+
+fn register_name(register: &Register) -> &'static str {
+    match register {
+        Register::AP => "AP",
+        Register::FP => "FP",
+    }
+}
+
+fn op1_addr_name(op1_addr: &Op1Addr) -> &'static str {
+    match op1_addr {
+        Op1Addr::Imm => "IMM",
+        Op1Addr::AP => "AP",
+        Op1Addr::FP => "FP",
+        Op1Addr::Op0 => "OP0",
+    }
+}
+
+fn res_name(res: &Res) -> &'static str {
+    match res {
+        Res::Op1 => "OP1",
+        Res::Add => "ADD",
+        Res::Mul => "MUL",
+        Res::Unconstrained => "UNCONSTRAINED",
+    }
+}
+
+fn pc_update_name(pc_update: &PcUpdate) -> &'static str {
+    match pc_update {
+        PcUpdate::Regular => "REGULAR",
+        PcUpdate::Jump => "JUMP",
+        PcUpdate::JumpRel => "JUMP_REL",
+        PcUpdate::Jnz => "JNZ",
+    }
+}
+
+fn ap_update_name(ap_update: &ApUpdate) -> &'static str {
+    match ap_update {
+        ApUpdate::Regular => "REGULAR",
+        ApUpdate::Add => "ADD",
+        ApUpdate::Add1 => "ADD1",
+        ApUpdate::Add2 => "ADD2",
+    }
+}
+
+fn fp_update_name(fp_update: &FpUpdate) -> &'static str {
+    match fp_update {
+        FpUpdate::Regular => "REGULAR",
+        FpUpdate::APPlus2 => "AP_PLUS2",
+        FpUpdate::Dst => "DST",
+    }
+}
+
+fn opcode_name(opcode: &Opcode) -> &'static str {
+    match opcode {
+        Opcode::NOp => "NOP",
+        Opcode::AssertEq => "ASSERT_EQ",
+        Opcode::Call => "CALL",
+        Opcode::Ret => "RET",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cairo_vm::{felt::Felt252, types::relocatable::MaybeRelocatable};
+
+    #[test]
+    fn decode_instruction_with_imm() {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        // [ap] = 123; ap++
+        vm.load_data(
+            Relocatable::from((0, 0)),
+            &vec![
+                MaybeRelocatable::Int(Felt252::new(0x480680017fff8000_u64)),
+                MaybeRelocatable::from(123),
+            ],
+        )
+        .unwrap();
+
+        let pc = Relocatable::from((0, 0));
+        let instruction = PyInstruction::new(&vm, pc, &decode_instruction_at(&vm, pc).unwrap());
+        assert_eq!(instruction.imm, Some(BigUint::from(123_u32)));
+        assert_eq!(instruction.size(), 2);
+        assert_eq!(instruction.opcode, "ASSERT_EQ");
+        assert_eq!(instruction.op1_addr, "IMM");
+        assert_eq!(instruction.ap_update, "ADD1");
+    }
+
+    #[test]
+    fn decode_instruction_ret() {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.load_data(
+            Relocatable::from((0, 0)),
+            &vec![MaybeRelocatable::Int(Felt252::new(0x208b7fff7fff7ffe_u64))],
+        )
+        .unwrap();
+
+        let pc = Relocatable::from((0, 0));
+        let instruction = PyInstruction::new(&vm, pc, &decode_instruction_at(&vm, pc).unwrap());
+        assert_eq!(instruction.imm, None);
+        assert_eq!(instruction.size(), 1);
+        assert_eq!(instruction.opcode, "RET");
+        assert_eq!(instruction.pc_update, "JUMP");
+        assert_eq!(instruction.fp_update, "DST");
+    }
+
+    #[test]
+    fn decode_non_integer_fails() {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.load_data(
+            Relocatable::from((0, 0)),
+            &vec![MaybeRelocatable::from((0, 0))],
+        )
+        .unwrap();
+
+        assert!(decode_instruction_at(&vm, (0, 0).into()).is_err());
+    }
+}
@@ -1,10 +1,24 @@
+mod abi;
+mod builtin_runner;
+mod cairo_pie;
 pub mod cairo_run;
 pub mod cairo_runner;
+#[cfg(feature = "cairo-1-hints")]
+mod casm_contract;
+mod checkpoint;
+mod coverage;
+mod debug_info;
 mod ecdsa;
+mod encoded_files;
+mod errors;
+mod identifiers;
 pub mod ids;
+mod instruction;
 mod instruction_location;
 mod memory;
 mod memory_segments;
+mod profiler;
+mod program;
 mod range_check;
 mod relocatable;
 mod run_context;
@@ -12,18 +26,45 @@ mod scope_manager;
 mod to_felt_or_relocatable;
 mod utils;
 mod vm_core;
+mod vm_state_diff;
+mod vm_state_files;
 
 #[cfg(all(feature = "extension-module", feature = "embedded-python"))]
 compile_error!("\"extension-module\" is incompatible with \"embedded-python\" as it inhibits linking with cpython");
 
-use cairo_runner::PyCairoRunner;
+use builtin_runner::PyBuiltinRunner;
+use cairo_pie::PyCairoPie;
+use cairo_runner::{PyCairoRunner, PyExecutionResources};
+use checkpoint::PyCheckpoint;
+use coverage::PyCoverage;
+use instruction::PyInstruction;
+use profiler::PyProfile;
+use program::PyProgram;
 use pyo3::prelude::*;
 use relocatable::PyRelocatable;
+use vm_state_files::{PyMemoryFileIterator, PyTraceFileIterator};
 
 #[pymodule]
-fn cairo_vm_py(_py: Python, m: &PyModule) -> PyResult<()> {
+fn cairo_vm_py(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyCairoRunner>()?;
     m.add_class::<PyRelocatable>()?;
+    m.add_class::<PyProgram>()?;
+    m.add_class::<PyInstruction>()?;
+    m.add_class::<PyCheckpoint>()?;
+    m.add_class::<PyProfile>()?;
+    m.add_class::<PyCoverage>()?;
+    m.add_class::<PyBuiltinRunner>()?;
+    m.add_class::<PyExecutionResources>()?;
+    m.add_class::<PyCairoPie>()?;
+    m.add_class::<PyTraceFileIterator>()?;
+    m.add_class::<PyMemoryFileIterator>()?;
+    m.add_function(wrap_pyfunction!(vm_state_files::read_trace_file, m)?)?;
+    m.add_function(wrap_pyfunction!(vm_state_files::read_memory_file, m)?)?;
+    m.add_function(wrap_pyfunction!(vm_state_files::iter_trace_file, m)?)?;
+    m.add_function(wrap_pyfunction!(vm_state_files::iter_memory_file, m)?)?;
+    m.add_function(wrap_pyfunction!(vm_state_files::compare_trace_files, m)?)?;
+    m.add_function(wrap_pyfunction!(vm_state_files::compare_memory_files, m)?)?;
+    errors::add_exceptions(py, m)?;
     Ok(())
 }
 
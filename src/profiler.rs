@@ -0,0 +1,433 @@
+use crate::{debug_info::ProgramDebugInfo, identifiers::ProgramIdentifiers};
+use cairo_vm::types::instruction::Opcode;
+use pyo3::{prelude::*, types::PyBytes};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+const UNKNOWN_FUNCTION: &str = "<unknown>";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FunctionInfo {
+    name: String,
+    filename: String,
+    start_line: u32,
+}
+
+/// Maps pc offsets to the function they belong to, preferring the scopes
+/// found in the debug info and falling back to the closest preceding function
+/// identifier.
+#[derive(Debug, Default)]
+pub(crate) struct FunctionMap {
+    debug_info: Rc<ProgramDebugInfo>,
+    starts: Vec<(usize, String)>,
+    start_pcs: HashMap<String, usize>,
+}
+
+impl FunctionMap {
+    pub fn new(identifiers: &ProgramIdentifiers, debug_info: Rc<ProgramDebugInfo>) -> Self {
+        let mut starts = identifiers
+            .functions()
+            .map(|(name, pc)| (pc, name.to_string()))
+            .collect::<Vec<_>>();
+        starts.sort_unstable();
+        let start_pcs = starts
+            .iter()
+            .map(|(pc, name)| (name.clone(), *pc))
+            .collect();
+        FunctionMap {
+            debug_info,
+            starts,
+            start_pcs,
+        }
+    }
+
+    fn function_name(&self, pc: usize) -> &str {
+        if let Some(name) = self.debug_info.function_name(pc) {
+            return name;
+        }
+        match self.starts.partition_point(|(start, _)| *start <= pc) {
+            0 => UNKNOWN_FUNCTION,
+            index => &self.starts[index - 1].1,
+        }
+    }
+
+    fn function_info(&self, name: &str) -> FunctionInfo {
+        let location = self
+            .start_pcs
+            .get(name)
+            .and_then(|pc| self.debug_info.instruction_location(*pc));
+        FunctionInfo {
+            name: name.to_string(),
+            filename: location
+                .map(|location| location.inst.input_file.filename.clone())
+                .unwrap_or_default(),
+            start_line: location.map_or(0, |location| location.inst.start_line),
+        }
+    }
+}
+
+/// Attributes every executed instruction to the call stack it ran in. The
+/// stack is tracked from the `call` and `ret` instructions, with functions
+/// stored as indexes into `functions`.
+#[derive(Debug, Clone)]
+pub(crate) struct Profiler {
+    function_map: Rc<FunctionMap>,
+    functions: Vec<FunctionInfo>,
+    function_ids: HashMap<usize, usize>,
+    name_ids: HashMap<String, usize>,
+    stack: Vec<usize>,
+    samples: HashMap<Vec<usize>, u64>,
+    calls: HashMap<(usize, usize), u64>,
+}
+
+impl Profiler {
+    pub fn new(function_map: Rc<FunctionMap>) -> Self {
+        Profiler {
+            function_map,
+            functions: Vec::new(),
+            function_ids: HashMap::new(),
+            name_ids: HashMap::new(),
+            stack: Vec::new(),
+            samples: HashMap::new(),
+            calls: HashMap::new(),
+        }
+    }
+
+    /// Records an executed instruction given the pc it ran at, its opcode and
+    /// the pc the VM moved to.
+    pub fn record_step(&mut self, pc: usize, opcode: &Opcode, next_pc: usize) {
+        let function = self.function_id(pc);
+        match self.stack.last_mut() {
+            Some(top) => *top = function,
+            None => self.stack.push(function),
+        }
+        match self.samples.get_mut(self.stack.as_slice()) {
+            Some(count) => *count += 1,
+            None => {
+                self.samples.insert(self.stack.clone(), 1);
+            }
+        }
+
+        match opcode {
+            Opcode::Call => {
+                let callee = self.function_id(next_pc);
+                *self.calls.entry((function, callee)).or_default() += 1;
+                self.stack.push(callee);
+            }
+            Opcode::Ret => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn profile(&self) -> PyProfile {
+        let mut samples = self
+            .samples
+            .iter()
+            .map(|(stack, count)| (stack.clone(), *count))
+            .collect::<Vec<_>>();
+        samples.sort_unstable();
+        PyProfile {
+            functions: self.functions.clone(),
+            samples,
+            calls: self.calls.clone(),
+        }
+    }
+
+    fn function_id(&mut self, pc: usize) -> usize {
+        if let Some(id) = self.function_ids.get(&pc) {
+            return *id;
+        }
+        let name = self.function_map.function_name(pc);
+        let id = match self.name_ids.get(name) {
+            Some(id) => *id,
+            None => {
+                let id = self.functions.len();
+                self.functions.push(self.function_map.function_info(name));
+                self.name_ids.insert(name.to_string(), id);
+                id
+            }
+        };
+        self.function_ids.insert(pc, id);
+        id
+    }
+}
+
+/// The result of a profiled run. Stacks are stored root first.
+#[pyclass(name = "Profile")]
+#[derive(Debug, Clone)]
+pub struct PyProfile {
+    functions: Vec<FunctionInfo>,
+    samples: Vec<(Vec<usize>, u64)>,
+    calls: HashMap<(usize, usize), u64>,
+}
+
+#[pymethods]
+impl PyProfile {
+    #[getter]
+    pub fn total_steps(&self) -> u64 {
+        self.samples.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Maps every executed function to its (inclusive, exclusive) step count.
+    #[getter]
+    pub fn functions(&self) -> HashMap<String, (u64, u64)> {
+        let mut inclusive = vec![0_u64; self.functions.len()];
+        let mut exclusive = vec![0_u64; self.functions.len()];
+        for (stack, count) in self.samples.iter() {
+            for function in stack.iter().collect::<HashSet<_>>() {
+                inclusive[*function] += *count;
+            }
+            if let Some(leaf) = stack.last() {
+                exclusive[*leaf] += *count;
+            }
+        }
+        self.functions
+            .iter()
+            .enumerate()
+            .map(|(id, function)| (function.name.clone(), (inclusive[id], exclusive[id])))
+            .collect()
+    }
+
+    /// Maps every (caller, callee) pair to the amount of calls made and the
+    /// inclusive step count of the callee when called from the caller.
+    #[getter]
+    pub fn call_edges(&self) -> HashMap<(String, String), (u64, u64)> {
+        let mut steps = HashMap::<(usize, usize), u64>::new();
+        for (stack, count) in self.samples.iter() {
+            let edges = stack
+                .windows(2)
+                .map(|edge| (edge[0], edge[1]))
+                .collect::<HashSet<_>>();
+            for edge in edges {
+                *steps.entry(edge).or_default() += *count;
+            }
+        }
+        self.calls
+            .iter()
+            .map(|(edge, calls)| {
+                (
+                    (
+                        self.functions[edge.0].name.clone(),
+                        self.functions[edge.1].name.clone(),
+                    ),
+                    (*calls, steps.get(edge).copied().unwrap_or_default()),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the profile in the folded stacks format used by flamegraph
+    /// tools, one `caller;callee count` line per stack.
+    pub fn to_folded(&self) -> String {
+        let mut lines = self
+            .samples
+            .iter()
+            .map(|(stack, count)| {
+                let names = stack
+                    .iter()
+                    .map(|id| self.functions[*id].name.as_str())
+                    .collect::<Vec<_>>();
+                format!("{} {count}", names.join(";"))
+            })
+            .collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines.iter().map(|line| format!("{line}\n")).collect()
+    }
+
+    /// Returns the profile encoded as an uncompressed pprof protobuf message.
+    pub fn to_pprof<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.encode_pprof())
+    }
+}
+
+impl PyProfile {
+    fn encode_pprof(&self) -> Vec<u8> {
+        let mut strings = StringTable::default();
+        let mut profile = Vec::new();
+
+        // sample_type
+        let mut value_type = Vec::new();
+        encode_uint(&mut value_type, 1, strings.index("steps"));
+        encode_uint(&mut value_type, 2, strings.index("count"));
+        encode_bytes(&mut profile, 1, &value_type);
+
+        // Locations and functions share ids, one per function
+        for (stack, count) in self.samples.iter() {
+            let mut sample = Vec::new();
+            let location_ids = stack
+                .iter()
+                .rev()
+                .map(|id| *id as u64 + 1)
+                .collect::<Vec<_>>();
+            encode_packed(&mut sample, 1, &location_ids);
+            encode_packed(&mut sample, 2, &[*count]);
+            encode_bytes(&mut profile, 2, &sample);
+        }
+
+        for (id, function) in self.functions.iter().enumerate() {
+            let id = id as u64 + 1;
+            let mut line = Vec::new();
+            encode_uint(&mut line, 1, id);
+            encode_uint(&mut line, 2, function.start_line as u64);
+            let mut location = Vec::new();
+            encode_uint(&mut location, 1, id);
+            encode_bytes(&mut location, 4, &line);
+            encode_bytes(&mut profile, 4, &location);
+        }
+
+        for (id, function) in self.functions.iter().enumerate() {
+            let mut encoded_function = Vec::new();
+            encode_uint(&mut encoded_function, 1, id as u64 + 1);
+            encode_uint(&mut encoded_function, 2, strings.index(&function.name));
+            encode_uint(&mut encoded_function, 3, strings.index(&function.name));
+            encode_uint(&mut encoded_function, 4, strings.index(&function.filename));
+            encode_uint(&mut encoded_function, 5, function.start_line as u64);
+            encode_bytes(&mut profile, 5, &encoded_function);
+        }
+
+        for string in strings.strings.iter() {
+            encode_bytes(&mut profile, 6, string.as_bytes());
+        }
+        profile
+    }
+}
+
+/// pprof's string table, whose first entry must be the empty string.
+struct StringTable {
+    strings: Vec<String>,
+    indexes: HashMap<String, u64>,
+}
+
+impl Default for StringTable {
+    fn default() -> Self {
+        StringTable {
+            strings: vec![String::new()],
+            indexes: HashMap::from([(String::new(), 0)]),
+        }
+    }
+}
+
+impl StringTable {
+    fn index(&mut self, string: &str) -> u64 {
+        if let Some(index) = self.indexes.get(string) {
+            return *index;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.push(string.to_string());
+        self.indexes.insert(string.to_string(), index);
+        index
+    }
+}
+
+const WIRE_TYPE_VARINT: u64 = 0;
+const WIRE_TYPE_LEN: u64 = 2;
+
+fn encode_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn encode_uint(buf: &mut Vec<u8>, field: u64, value: u64) {
+    encode_varint(buf, field << 3 | WIRE_TYPE_VARINT);
+    encode_varint(buf, value);
+}
+
+fn encode_bytes(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    encode_varint(buf, field << 3 | WIRE_TYPE_LEN);
+    encode_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn encode_packed(buf: &mut Vec<u8>, field: u64, values: &[u64]) {
+    let mut packed = Vec::new();
+    for value in values {
+        encode_varint(&mut packed, *value);
+    }
+    encode_bytes(buf, field, &packed);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn function_map() -> Rc<FunctionMap> {
+        Rc::new(FunctionMap {
+            debug_info: Rc::default(),
+            starts: vec![(0, "main".to_string()), (10, "f".to_string())],
+            start_pcs: HashMap::from([("main".to_string(), 0), ("f".to_string(), 10)]),
+        })
+    }
+
+    #[test]
+    fn function_name_falls_back_to_identifiers() {
+        let function_map = function_map();
+        assert_eq!(function_map.function_name(0), "main");
+        assert_eq!(function_map.function_name(9), "main");
+        assert_eq!(function_map.function_name(12), "f");
+    }
+
+    #[test]
+    fn record_steps_along_call_and_ret() {
+        let mut profiler = Profiler::new(function_map());
+        profiler.record_step(0, &Opcode::AssertEq, 2);
+        profiler.record_step(2, &Opcode::Call, 10);
+        profiler.record_step(10, &Opcode::AssertEq, 11);
+        profiler.record_step(11, &Opcode::Ret, 4);
+        profiler.record_step(4, &Opcode::Ret, 0);
+
+        let profile = profiler.profile();
+        assert_eq!(profile.total_steps(), 5);
+        assert_eq!(
+            profile.functions(),
+            HashMap::from([("main".to_string(), (5, 3)), ("f".to_string(), (2, 2))])
+        );
+        assert_eq!(
+            profile.call_edges(),
+            HashMap::from([(("main".to_string(), "f".to_string()), (1, 2))])
+        );
+        assert_eq!(profile.to_folded(), "main 3\nmain;f 2\n");
+    }
+
+    #[test]
+    fn recursive_calls_are_counted_once_per_step() {
+        let mut profiler = Profiler::new(function_map());
+        profiler.record_step(0, &Opcode::Call, 10);
+        profiler.record_step(10, &Opcode::Call, 10);
+        profiler.record_step(10, &Opcode::Ret, 11);
+        profiler.record_step(11, &Opcode::Ret, 1);
+
+        let profile = profiler.profile();
+        assert_eq!(profile.functions()["f"], (3, 3));
+        assert_eq!(
+            profile.call_edges()[&("f".to_string(), "f".to_string())],
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn varint_encoding() {
+        let mut buf = Vec::new();
+        encode_varint(&mut buf, 1);
+        encode_varint(&mut buf, 300);
+        assert_eq!(buf, vec![0x01, 0xac, 0x02]);
+    }
+
+    #[test]
+    fn pprof_string_table_starts_with_empty_string() {
+        let mut profiler = Profiler::new(function_map());
+        profiler.record_step(0, &Opcode::Ret, 0);
+        let encoded = profiler.profile().encode_pprof();
+        // The first field is the sample type
+        assert_eq!(encoded[0], 1 << 3 | WIRE_TYPE_LEN as u8);
+        // The first string table entry is empty
+        let empty_string = [6 << 3 | WIRE_TYPE_LEN as u8, 0];
+        assert!(encoded.windows(2).any(|window| window == empty_string));
+    }
+}
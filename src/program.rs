@@ -0,0 +1,228 @@
+use crate::{debug_info::ProgramDebugInfo, identifiers::ProgramIdentifiers, utils::to_py_error};
+use cairo_vm::{
+    serde::deserialize_program::{
+        deserialize_array_of_bigint_hex, parse_program_json, Attribute, BuiltinName, DebugInfo,
+        HintParams, Identifier, Member, ProgramJson, ReferenceManager,
+    },
+    types::{program::Program, relocatable::MaybeRelocatable},
+};
+use num_bigint::BigUint;
+use pyo3::{exceptions::PyTypeError, exceptions::PyValueError, prelude::*};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::PathBuf, rc::Rc};
+
+/// A compiled program as found in its JSON, which is only parsed once. The
+/// identifiers and debug info are kept as JSON values, as both cairo-vm and
+/// this crate read parts of them that the other doesn't.
+#[derive(Deserialize)]
+struct RawProgram {
+    prime: String,
+    builtins: Vec<BuiltinName>,
+    #[serde(deserialize_with = "deserialize_array_of_bigint_hex")]
+    data: Vec<MaybeRelocatable>,
+    identifiers: HashMap<String, Value>,
+    hints: HashMap<usize, Vec<HintParams>>,
+    reference_manager: ReferenceManager,
+    #[serde(default)]
+    attributes: Vec<Attribute>,
+    debug_info: Option<Value>,
+}
+
+/// A compiled program, parsed once so that it can be shared between runners.
+#[pyclass(unsendable, name = "Program")]
+#[derive(Clone)]
+pub struct PyProgram {
+    pub(crate) program: Program,
+    pub(crate) identifiers: Rc<ProgramIdentifiers>,
+    pub(crate) debug_info: Rc<ProgramDebugInfo>,
+    pub(crate) struct_types: Rc<HashMap<String, HashMap<String, Member>>>,
+    main: Option<usize>,
+    builtins: Vec<String>,
+    prime: BigUint,
+    hints: HashMap<usize, Vec<String>>,
+}
+
+#[pymethods]
+impl PyProgram {
+    /// Parses a compiled program given its JSON as either str or bytes.
+    #[new]
+    #[pyo3(signature = (program, entrypoint="main".to_string()))]
+    pub fn new(program: &PyAny, entrypoint: Option<String>) -> PyResult<Self> {
+        if let Ok(program_json) = program.extract::<&str>() {
+            PyProgram::from_json(program_json.as_bytes(), entrypoint.as_deref())
+        } else if let Ok(program_json) = program.extract::<&[u8]>() {
+            PyProgram::from_json(program_json, entrypoint.as_deref())
+        } else {
+            Err(PyTypeError::new_err("program must be str or bytes"))
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (path, entrypoint="main".to_string()))]
+    pub fn from_file(path: PathBuf, entrypoint: Option<String>) -> PyResult<Self> {
+        let program_json = fs::read(path)?;
+        PyProgram::from_json(&program_json, entrypoint.as_deref())
+    }
+
+    #[getter]
+    pub fn builtins(&self) -> Vec<String> {
+        self.builtins.clone()
+    }
+
+    /// The pc of the program's entrypoint, if any.
+    #[getter]
+    pub fn main(&self) -> Option<usize> {
+        self.main
+    }
+
+    /// Maps every identifier to its type.
+    #[getter]
+    pub fn identifiers(&self) -> HashMap<String, Option<String>> {
+        self.program
+            .iter_identifiers()
+            .map(|(name, identifier)| (name.to_string(), identifier.type_.clone()))
+            .collect()
+    }
+
+    /// Maps every pc to the code of the hints that run before it.
+    #[getter]
+    pub fn hints(&self) -> HashMap<usize, Vec<String>> {
+        self.hints.clone()
+    }
+
+    #[getter]
+    pub fn constants(&self) -> HashMap<String, BigUint> {
+        self.identifiers.constants()
+    }
+
+    #[getter]
+    pub fn prime(&self) -> BigUint {
+        self.prime.clone()
+    }
+}
+
+impl PyProgram {
+    pub(crate) fn from_json(program_json: &[u8], entrypoint: Option<&str>) -> PyResult<Self> {
+        let raw: RawProgram = serde_json::from_slice(program_json).map_err(json_error)?;
+
+        let prime = BigUint::parse_bytes(raw.prime.trim_start_matches("0x").as_bytes(), 16)
+            .ok_or_else(|| PyValueError::new_err(format!("Invalid prime '{}'", raw.prime)))?;
+        let builtins: Vec<String> = raw
+            .builtins
+            .iter()
+            .map(|builtin| builtin.name().trim_end_matches("_builtin").to_string())
+            .collect();
+        let hints: HashMap<usize, Vec<String>> = raw
+            .hints
+            .iter()
+            .map(|(pc, pc_hints)| (*pc, pc_hints.iter().map(|hint| hint.code.clone()).collect()))
+            .collect();
+        let debug_info = ProgramDebugInfo::new(raw.debug_info.as_ref())?;
+
+        let mut identifiers = HashMap::with_capacity(raw.identifiers.len());
+        for (name, identifier) in &raw.identifiers {
+            let identifier = Identifier::deserialize(identifier).map_err(json_error)?;
+            identifiers.insert(name.clone(), identifier);
+        }
+        let program = parse_program_json(
+            ProgramJson {
+                prime: raw.prime,
+                builtins: raw.builtins,
+                data: raw.data,
+                identifiers,
+                hints: raw.hints,
+                reference_manager: raw.reference_manager,
+                attributes: raw.attributes,
+                debug_info: raw
+                    .debug_info
+                    .as_ref()
+                    .map(DebugInfo::deserialize)
+                    .transpose()
+                    .map_err(json_error)?,
+            },
+            entrypoint,
+        )
+        .map_err(to_py_error)?;
+
+        let struct_types = program
+            .iter_identifiers()
+            .filter_map(|(path, identifier)| match identifier.type_.as_deref() {
+                Some("struct") => Some((path.to_string(), identifier.members.clone().unwrap())),
+                _ => None,
+            })
+            .collect();
+        let identifiers = ProgramIdentifiers::new(&program, &raw.identifiers);
+        // The same lookup cairo-vm does for the entrypoint it already checked exists
+        let main = entrypoint
+            .and_then(|entrypoint| program.get_identifier(&format!("__main__.{entrypoint}")))
+            .and_then(|identifier| identifier.pc);
+
+        Ok(PyProgram {
+            program,
+            identifiers: Rc::new(identifiers),
+            debug_info: Rc::new(debug_info),
+            struct_types: Rc::new(struct_types),
+            main,
+            builtins,
+            prime,
+            hints,
+        })
+    }
+}
+
+fn json_error(err: serde_json::Error) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vm_core::CAIRO_PRIME;
+
+    #[test]
+    fn load_program_from_file() {
+        let program =
+            PyProgram::from_file(PathBuf::from("cairo_programs/array_sum.json"), None).unwrap();
+        assert_eq!(program.builtins(), vec!["output".to_string()]);
+        assert_eq!(program.prime(), *CAIRO_PRIME);
+        assert_eq!(program.main(), None);
+        assert_eq!(
+            program.identifiers().get("__main__.array_sum"),
+            Some(&Some("function".to_string()))
+        );
+        assert_eq!(
+            program.constants().get("__main__.main.ARRAY_SIZE"),
+            Some(&BigUint::from(3_u32))
+        );
+    }
+
+    #[test]
+    fn load_program_from_str_and_bytes() {
+        let program_json = fs::read_to_string("cairo_programs/not_main.json").unwrap();
+        Python::with_gil(|py| {
+            let from_str = PyProgram::new(
+                program_json.to_object(py).as_ref(py),
+                Some("main".to_string()),
+            )
+            .unwrap();
+            assert_eq!(from_str.main(), Some(3));
+
+            let bytes = pyo3::types::PyBytes::new(py, program_json.as_bytes());
+            let from_bytes = PyProgram::new(bytes, Some("not_main".to_string())).unwrap();
+            assert_eq!(from_bytes.main(), Some(0));
+
+            assert!(PyProgram::new(1.to_object(py).as_ref(py), None).is_err());
+        });
+    }
+
+    #[test]
+    fn program_hints() {
+        let program =
+            PyProgram::from_file(PathBuf::from("cairo_programs/assert_not_zero.json"), None)
+                .unwrap();
+        let hints = program.hints();
+        assert_eq!(hints.len(), 1);
+        assert!(hints.values().next().unwrap()[0].contains("assert_integer(ids.value)"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::errors::vm_error_to_py;
 use cairo_vm::{
     felt::Felt252, types::errors::math_errors::MathError,
     vm::errors::vm_errors::VirtualMachineError,
@@ -12,8 +13,10 @@ macro_rules! pycell {
         PyCell::new($py, $val)?
     };
 }
-pub fn to_py_error<T: Display>(error: T) -> PyErr {
-    PyValueError::new_err(error.to_string())
+/// Converts an error into a Python exception, using the classes in `errors`
+/// for the VM's errors and ValueError for anything else.
+pub fn to_py_error<T: Display + 'static>(error: T) -> PyErr {
+    vm_error_to_py(&error).unwrap_or_else(|| PyValueError::new_err(error.to_string()))
 }
 
 #[macro_export]
@@ -1,6 +1,10 @@
 use crate::any_box;
+use crate::coverage::CoverageTracker;
+use crate::debug_info::HintDebugInfo;
 use crate::ecdsa::PySignature;
 use crate::ids::PyIds;
+use crate::instruction::{decode_instruction_at, PyInstruction};
+use crate::profiler::Profiler;
 use crate::pycell;
 use crate::run_context::PyRunContext;
 use crate::scope_manager::{PyEnterScope, PyExitScope};
@@ -11,10 +15,15 @@ use crate::{
     relocatable::PyRelocatable,
 };
 use cairo_vm::felt::Felt252;
+use cairo_vm::hint_processor::builtin_hint_processor::{
+    hint_code::VERIFY_ECDSA_SIGNATURE,
+    hint_utils::{get_integer_from_var_name, get_ptr_from_var_name},
+};
 use cairo_vm::hint_processor::hint_processor_definition::HintProcessor;
 use cairo_vm::serde::deserialize_program::Member;
 use cairo_vm::types::exec_scope::ExecutionScopes;
-use cairo_vm::vm::errors::hint_errors::HintError;
+use cairo_vm::types::relocatable::Relocatable;
+use cairo_vm::vm::errors::{exec_scope_errors::ExecScopeError, hint_errors::HintError};
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::{
     hint_processor::builtin_hint_processor::builtin_hint_processor_definition::HintProcessorData,
@@ -22,11 +31,11 @@ use cairo_vm::{
 };
 use lazy_static::lazy_static;
 use num_bigint::BigUint;
-use pyo3::{pyclass, pymethods, PyObject, ToPyObject};
+use pyo3::{pyclass, pymethods, Py, PyAny, PyObject, PyResult, ToPyObject};
 use pyo3::{types::PyDict, Python};
 use pyo3::{PyCell, PyErr};
 use std::any::Any;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::{cell::RefCell, rc::Rc};
 
 const GLOBAL_NAMES: [&str; 18] = [
@@ -50,6 +59,14 @@ const GLOBAL_NAMES: [&str; 18] = [
     "__name__",
 ];
 
+/// The scope variable holding the globals of the Python hints run in that
+/// scope. They are kept between hints so that only what changes is updated,
+/// and are dropped along with the scope.
+pub(crate) const HINT_NAMESPACE: &str = "__hint_namespace__";
+
+/// The filename of the frames run by a Python hint.
+pub(crate) const HINT_FILENAME: &str = "<hint>";
+
 lazy_static! {
     pub static ref CAIRO_PRIME: BigUint = Felt252::prime();
 }
@@ -59,6 +76,63 @@ lazy_static! {
 pub struct PyVM {
     pub(crate) vm: Rc<RefCell<VirtualMachine>>,
     pub(crate) failed_hint_index: Option<usize>,
+    pub(crate) step_hooks: StepHooks,
+    pub(crate) profiler: Option<Profiler>,
+    pub(crate) coverage: Option<CoverageTracker>,
+    /// The compiled code of the program's Python hints, keyed by pc offset and
+    /// hint index.
+    pub(crate) compiled_hints: HashMap<(usize, usize), CompiledHint>,
+    /// Python callables run instead of the hints with the same code.
+    pub(crate) hint_functions: HashMap<String, PyObject>,
+    /// The ECDSA signatures added to the signature builtin, which keeps them
+    /// private.
+    pub(crate) signatures: HashMap<Relocatable, (Felt252, Felt252)>,
+    /// The addresses accessed before the VM was rebuilt from a checkpoint,
+    /// which it only lets mark as accessed once the run finishes.
+    pub(crate) accessed_addresses: Vec<Relocatable>,
+}
+
+/// The globals of the Python hints run in a scope.
+struct HintNamespace {
+    globals: Py<PyDict>,
+    /// The scope variables held by the globals.
+    scope_names: HashSet<String>,
+}
+
+/// A Python hint's code object, along with the names it binds.
+#[derive(Clone)]
+pub(crate) struct CompiledHint {
+    pub(crate) code: PyObject,
+    names: Rc<HintNames>,
+}
+
+/// The names a hint's code assigns and deletes in its globals, taken from the
+/// `co_names` operands of its store and delete opcodes. Names only written
+/// through `globals()` aren't seen.
+#[derive(Default)]
+struct HintNames {
+    assigned: HashSet<String>,
+    deleted: HashSet<String>,
+}
+
+/// What runs a Python hint: its compiled code, or a callable registered for it.
+enum HintCode {
+    Compiled(CompiledHint),
+    Function(PyObject),
+}
+
+/// Python callables run around every executed instruction. Each one receives
+/// the current `RunContext` and the decoded `Instruction`.
+#[derive(Clone, Default)]
+pub(crate) struct StepHooks {
+    pub(crate) pre_step: Vec<PyObject>,
+    pub(crate) post_step: Vec<PyObject>,
+}
+
+impl StepHooks {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pre_step.is_empty() && self.post_step.is_empty()
+    }
 }
 
 #[pymethods]
@@ -72,9 +146,22 @@ impl PyVM {
 
 impl PyVM {
     pub fn new(trace_enabled: bool) -> PyVM {
+        PyVM::from_vm(Rc::new(RefCell::new(VirtualMachine::new(trace_enabled))))
+    }
+
+    /// Wraps a VM that is shared with another PyVM, without copying its hooks,
+    /// profiler or hint state.
+    pub(crate) fn from_vm(vm: Rc<RefCell<VirtualMachine>>) -> PyVM {
         PyVM {
-            vm: Rc::new(RefCell::new(VirtualMachine::new(trace_enabled))),
+            vm,
             failed_hint_index: None,
+            step_hooks: StepHooks::default(),
+            profiler: None,
+            coverage: None,
+            compiled_hints: HashMap::new(),
+            hint_functions: HashMap::new(),
+            signatures: HashMap::new(),
+            accessed_addresses: Vec::new(),
         }
     }
 
@@ -82,6 +169,7 @@ impl PyVM {
         Rc::clone(&self.vm)
     }
 
+    #[cfg(test)]
     pub(crate) fn execute_hint(
         &mut self,
         hint_data: &HintProcessorData,
@@ -90,10 +178,125 @@ impl PyVM {
         constants: &HashMap<String, Felt252>,
         struct_types: Rc<HashMap<String, HashMap<String, Member>>>,
         static_locals: Option<&HashMap<String, PyObject>>,
+    ) -> Result<(), PyErr> {
+        let (code, globals) = Python::with_gil(|py| -> PyResult<_> {
+            let code = CompiledHint::new(py, &hint_data.code)?;
+            let globals = self.new_hint_namespace(py)?;
+            add_scope_locals(globals.as_ref(py), exec_scopes)?;
+            Ok((code, globals))
+        })?;
+        self.execute_compiled_hint(
+            &code,
+            &globals,
+            hint_data,
+            hint_locals,
+            exec_scopes,
+            constants,
+            struct_types,
+            static_locals,
+        )
+    }
+
+    /// Returns the compiled code of the hint at `key`, compiling it on its first run.
+    fn compiled_hint(&mut self, key: (usize, usize), code: &str) -> PyResult<CompiledHint> {
+        if let Some(code) = self.compiled_hints.get(&key) {
+            return Ok(code.clone());
+        }
+        let code = Python::with_gil(|py| CompiledHint::new(py, code))?;
+        self.compiled_hints.insert(key, code.clone());
+        Ok(code)
+    }
+
+    /// Builds the globals of the hints run in a scope, holding the objects that
+    /// don't change between hints.
+    fn new_hint_namespace(&self, py: Python) -> PyResult<Py<PyDict>> {
+        let memory = PyMemory::new(self);
+        let segments = PySegmentManager::new(self, memory.clone());
+        let range_check_builtin = PyRangeCheck::from((*self.vm).borrow().get_range_check_builtin());
+        let prime: BigUint = CAIRO_PRIME.clone();
+        let to_felt_or_relocatable = ToFeltOrRelocatableFunc;
+
+        // This line imports Python builtins. If not imported, this will run only with Python 3.10
+        let globals = py.import("__main__")?.dict().copy()?;
+
+        globals.set_item("memory", pycell!(py, memory))?;
+        globals.set_item("segments", pycell!(py, segments))?;
+        globals.set_item("range_check_builtin", range_check_builtin)?;
+        globals.set_item("PRIME", prime)?;
+        globals.set_item(
+            "to_felt_or_relocatable",
+            pycell!(py, to_felt_or_relocatable),
+        )?;
+        Ok(globals.into())
+    }
+
+    /// Returns the globals of the current scope's hints, holding its Python
+    /// variables. Only the variables that other hints changed since the last
+    /// Python hint are updated, and those they deleted or replaced with Rust
+    /// values are removed.
+    fn hint_namespace(
+        &self,
+        py: Python,
+        exec_scopes: &mut ExecutionScopes,
+    ) -> PyResult<Py<PyDict>> {
+        let variables: HashMap<String, PyObject> = exec_scopes
+            .get_local_variables()
+            .map_err(to_py_error)?
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .downcast_ref::<PyObject>()
+                    .map(|value| (name.clone(), value.clone_ref(py)))
+            })
+            .collect();
+
+        let scope = exec_scopes.get_local_variables_mut().map_err(to_py_error)?;
+        if !scope
+            .get(HINT_NAMESPACE)
+            .map_or(false, |namespace| namespace.is::<HintNamespace>())
+        {
+            scope.insert(
+                HINT_NAMESPACE.to_string(),
+                any_box!(HintNamespace {
+                    globals: self.new_hint_namespace(py)?,
+                    scope_names: HashSet::new(),
+                }),
+            );
+        }
+        let namespace = hint_namespace_mut(scope)?;
+
+        let globals = namespace.globals.as_ref(py);
+        for name in namespace.scope_names.iter() {
+            if !variables.contains_key(name) && globals.contains(name)? {
+                globals.del_item(name)?;
+            }
+        }
+        for (name, value) in variables.iter() {
+            if !globals
+                .get_item(name)
+                .map_or(false, |current| current.is(value))
+            {
+                globals.set_item(name, value)?;
+            }
+        }
+        namespace.scope_names = variables.into_keys().collect();
+        Ok(namespace.globals.clone_ref(py))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_compiled_hint(
+        &mut self,
+        hint: &CompiledHint,
+        globals: &Py<PyDict>,
+        hint_data: &HintProcessorData,
+        hint_locals: &mut HashMap<String, PyObject>,
+        exec_scopes: &mut ExecutionScopes,
+        constants: &HashMap<String, Felt252>,
+        struct_types: Rc<HashMap<String, HashMap<String, Member>>>,
+        static_locals: Option<&HashMap<String, PyObject>>,
     ) -> Result<(), PyErr> {
         Python::with_gil(|py| -> Result<(), PyErr> {
-            let memory = PyMemory::new(self);
-            let segments = PySegmentManager::new(self, memory.clone());
+            let globals = globals.as_ref(py);
             let ap = PyRelocatable::from((*self.vm).borrow().get_ap());
             let fp = PyRelocatable::from((*self.vm).borrow().get_fp());
             let ids = PyIds::new(
@@ -105,31 +308,15 @@ impl PyVM {
             );
             let enter_scope = pycell!(py, PyEnterScope::new());
             let exit_scope = pycell!(py, PyExitScope::new());
-            let range_check_builtin =
-                PyRangeCheck::from((*self.vm).borrow().get_range_check_builtin());
             let ecdsa_builtin = pycell!(py, PySignature::new());
-            let prime: BigUint = CAIRO_PRIME.clone();
-            let to_felt_or_relocatable = ToFeltOrRelocatableFunc;
-
-            // This line imports Python builtins. If not imported, this will run only with Python 3.10
-            let globals = py.import("__main__")?.dict().copy()?;
-
-            add_scope_locals(globals, exec_scopes)?;
 
-            globals.set_item("memory", pycell!(py, memory))?;
-            globals.set_item("segments", pycell!(py, segments))?;
             globals.set_item("ap", pycell!(py, ap))?;
             globals.set_item("fp", pycell!(py, fp))?;
-            globals.set_item("ids", pycell!(py, ids))?;
+            let ids = pycell!(py, ids);
+            globals.set_item("ids", ids)?;
             globals.set_item("vm_enter_scope", enter_scope)?;
             globals.set_item("vm_exit_scope", exit_scope)?;
-            globals.set_item("range_check_builtin", range_check_builtin)?;
             globals.set_item("ecdsa_builtin", ecdsa_builtin)?;
-            globals.set_item("PRIME", prime)?;
-            globals.set_item(
-                "to_felt_or_relocatable",
-                pycell!(py, to_felt_or_relocatable),
-            )?;
 
             for (name, pyobj) in hint_locals.iter() {
                 globals.set_item(name, pyobj)?;
@@ -141,9 +328,17 @@ impl PyVM {
                 }
             }
 
-            py.run(&hint_data.code, Some(globals), None)?;
-
-            update_scope_hint_locals(exec_scopes, hint_locals, static_locals, globals, py);
+            py.import("builtins")?
+                .getattr("exec")?
+                .call1((hint.code.as_ref(py), globals))?;
+            update_scope_hint_locals(
+                exec_scopes,
+                hint_locals,
+                static_locals,
+                &hint.names,
+                globals,
+                py,
+            )?;
 
             if self.vm.borrow_mut().get_signature_builtin().is_ok() {
                 ecdsa_builtin
@@ -155,14 +350,44 @@ impl PyVM {
                             .map_err(to_py_error)?,
                     )
                     .map_err(to_py_error)?;
+                self.signatures.extend(ecdsa_builtin.borrow().signatures());
             }
+
             enter_scope.borrow().update_scopes(exec_scopes)?;
-            exit_scope.borrow().update_scopes(exec_scopes)
+            exit_scope.borrow().update_scopes(exec_scopes)?;
+            Ok(())
         })?;
 
         Ok(())
     }
 
+    /// Calls the function registered for a hint, which doesn't use the globals
+    /// of the Python hints. Of the VM, it only gets the `run_context`.
+    fn execute_hint_function(
+        &self,
+        function: &PyObject,
+        hint_data: &HintProcessorData,
+        exec_scopes: &mut ExecutionScopes,
+        constants: &HashMap<String, Felt252>,
+        struct_types: Rc<HashMap<String, HashMap<String, Member>>>,
+    ) -> Result<(), PyErr> {
+        Python::with_gil(|py| {
+            let vm = PyVM::from_vm(self.get_vm());
+            let ids = PyIds::new(
+                self,
+                &hint_data.ids_data,
+                &hint_data.ap_tracking,
+                constants,
+                struct_types,
+            );
+            let memory = PyMemory::new(self);
+            let scopes = scope_variables(py, exec_scopes)?;
+            function.call1(py, (vm, pycell!(py, ids), pycell!(py, memory), scopes))?;
+            update_scope_variables(exec_scopes, scopes, py);
+            Ok(())
+        })
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn step_hint(
         &mut self,
@@ -178,15 +403,20 @@ impl PyVM {
 
         if let Some(hint_list) = hint_data_dictionary.get(&pc_offset) {
             for (hint_index, hint_data) in hint_list.iter().enumerate() {
-                if self
-                    .should_run_py_hint(
-                        hint_executor,
-                        exec_scopes,
-                        hint_data,
-                        constants,
-                        hint_index,
-                    )
-                    .map_err(to_py_error)?
+                let hint_function = hint_data
+                    .downcast_ref::<HintProcessorData>()
+                    .and_then(|hint_data| self.hint_functions.get(&hint_data.code))
+                    .cloned();
+                if hint_function.is_some()
+                    || self
+                        .should_run_py_hint(
+                            hint_executor,
+                            exec_scopes,
+                            hint_data,
+                            constants,
+                            hint_index,
+                        )
+                        .map_err(to_py_error)?
                 {
                     let hint_data = hint_data
                         .downcast_ref::<HintProcessorData>()
@@ -198,18 +428,43 @@ impl PyVM {
                         })
                         .map_err(to_py_error)?;
 
-                    if let Err(hint_error) = self.execute_hint(
-                        hint_data,
-                        hint_locals,
-                        exec_scopes,
-                        constants,
-                        Rc::clone(&struct_types),
-                        static_locals,
-                    ) {
+                    let code = match hint_function {
+                        Some(function) => Ok(HintCode::Function(function)),
+                        None => self
+                            .compiled_hint((pc_offset, hint_index), &hint_data.code)
+                            .map(HintCode::Compiled),
+                    };
+                    let result = code.and_then(|code| match code {
+                        HintCode::Compiled(hint) => {
+                            let globals =
+                                Python::with_gil(|py| self.hint_namespace(py, exec_scopes))?;
+                            self.execute_compiled_hint(
+                                &hint,
+                                &globals,
+                                hint_data,
+                                hint_locals,
+                                exec_scopes,
+                                constants,
+                                Rc::clone(&struct_types),
+                                static_locals,
+                            )
+                        }
+                        HintCode::Function(function) => self.execute_hint_function(
+                            &function,
+                            hint_data,
+                            exec_scopes,
+                            constants,
+                            Rc::clone(&struct_types),
+                        ),
+                    });
+                    if let Err(hint_error) = result {
                         self.failed_hint_index = Some(hint_index);
                         return Err(hint_error);
                     }
                 }
+                if let Some(coverage) = self.coverage.as_mut() {
+                    coverage.record_hint(pc_offset, hint_index);
+                }
             }
         }
 
@@ -236,7 +491,57 @@ impl PyVM {
             constants,
             static_locals,
         )?;
-        self.vm.borrow_mut().step_instruction().map_err(to_py_error)
+        let pc = self.vm.borrow().get_pc();
+        if self.step_hooks.is_empty() && self.profiler.is_none() {
+            self.vm
+                .borrow_mut()
+                .step_instruction()
+                .map_err(to_py_error)?;
+            self.record_coverage(pc.offset);
+            return Ok(());
+        }
+
+        let instruction = decode_instruction_at(&self.vm.borrow(), pc)?;
+        // Hooks get the decoded instruction, which isn't needed when only profiling
+        let py_instruction = (!self.step_hooks.is_empty())
+            .then(|| PyInstruction::new(&self.vm.borrow(), pc, &instruction));
+        if let Some(py_instruction) = &py_instruction {
+            self.call_step_hooks(&self.step_hooks.pre_step, py_instruction)?;
+        }
+        self.vm
+            .borrow_mut()
+            .step_instruction()
+            .map_err(to_py_error)?;
+        self.record_coverage(pc.offset);
+        if let Some(profiler) = self.profiler.as_mut() {
+            let next_pc = self.vm.borrow().get_pc();
+            profiler.record_step(pc.offset, &instruction.opcode, next_pc.offset);
+        }
+        match &py_instruction {
+            Some(py_instruction) => {
+                self.call_step_hooks(&self.step_hooks.post_step, py_instruction)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Counts a pc as covered, once its instruction was run successfully.
+    fn record_coverage(&mut self, pc: usize) {
+        if let Some(coverage) = self.coverage.as_mut() {
+            coverage.record_pc(pc);
+        }
+    }
+
+    fn call_step_hooks(&self, hooks: &[PyObject], instruction: &PyInstruction) -> PyResult<()> {
+        if hooks.is_empty() {
+            return Ok(());
+        }
+        Python::with_gil(|py| {
+            for hook in hooks {
+                hook.call1(py, (self.run_context(), instruction.clone()))?;
+            }
+            Ok(())
+        })
     }
 
     fn should_run_py_hint(
@@ -249,7 +554,16 @@ impl PyVM {
     ) -> Result<bool, VirtualMachineError> {
         let mut vm = self.vm.borrow_mut();
         match hint_executor.execute_hint(&mut vm, exec_scopes, hint_data, constants) {
-            Ok(()) => Ok(false),
+            Ok(()) => {
+                if let Some(hint_data) = hint_data.downcast_ref::<HintProcessorData>() {
+                    if hint_data.code == VERIFY_ECDSA_SIGNATURE {
+                        let (address, signature) = added_signature(&vm, hint_data)
+                            .map_err(|e| VirtualMachineError::Hint(Box::new((hint_index, e))))?;
+                        self.signatures.insert(address, signature);
+                    }
+                }
+                Ok(false)
+            }
             Err(HintError::UnknownHint(_)) => Ok(true),
             Err(e) => {
                 self.failed_hint_index = Some(hint_index);
@@ -259,6 +573,123 @@ impl PyVM {
     }
 }
 
+/// Reads the signature that cairo-vm's `verify_ecdsa_signature` hint added,
+/// the same way the hint does.
+fn added_signature(
+    vm: &VirtualMachine,
+    hint_data: &HintProcessorData,
+) -> Result<(Relocatable, (Felt252, Felt252)), HintError> {
+    let ids_data = &hint_data.ids_data;
+    let ap_tracking = &hint_data.ap_tracking;
+    let signature_r = get_integer_from_var_name("signature_r", vm, ids_data, ap_tracking)?;
+    let signature_s = get_integer_from_var_name("signature_s", vm, ids_data, ap_tracking)?;
+    let ecdsa_ptr = get_ptr_from_var_name("ecdsa_ptr", vm, ids_data, ap_tracking)?;
+    Ok((
+        ecdsa_ptr,
+        (signature_r.into_owned(), signature_s.into_owned()),
+    ))
+}
+
+impl CompiledHint {
+    /// Compiles a hint's code under a known filename, so that its frames can be
+    /// told apart in the traceback of a failed hint.
+    fn new(py: Python, code: &str) -> PyResult<CompiledHint> {
+        let code =
+            py.import("builtins")?
+                .getattr("compile")?
+                .call1((code, HINT_FILENAME, "exec"))?;
+        Ok(CompiledHint {
+            code: code.into(),
+            names: Rc::new(HintNames::of(py, code)?),
+        })
+    }
+}
+
+impl HintNames {
+    /// Reads the names bound by `code` and by the functions it defines, which
+    /// can only bind the globals they declare `global`.
+    fn of(py: Python, code: &PyAny) -> PyResult<HintNames> {
+        let get_instructions = py.import("dis")?.getattr("get_instructions")?;
+        let code_type = py.import("types")?.getattr("CodeType")?;
+        let mut names = HintNames::default();
+        let mut codes = vec![code];
+        while let Some(code) = codes.pop() {
+            for instruction in get_instructions.call1((code,))?.iter()? {
+                let instruction = instruction?;
+                let names = match instruction.getattr("opname")?.extract::<&str>()? {
+                    "STORE_NAME" | "STORE_GLOBAL" => &mut names.assigned,
+                    "DELETE_NAME" | "DELETE_GLOBAL" => &mut names.deleted,
+                    _ => continue,
+                };
+                names.insert(instruction.getattr("argval")?.extract()?);
+            }
+            for constant in code.getattr("co_consts")?.iter()? {
+                let constant = constant?;
+                if constant.is_instance(code_type)? {
+                    codes.push(constant);
+                }
+            }
+        }
+        Ok(names)
+    }
+}
+
+/// Returns the globals of the current scope's Python hints.
+fn hint_namespace_mut(scope: &mut HashMap<String, Box<dyn Any>>) -> PyResult<&mut HintNamespace> {
+    scope
+        .get_mut(HINT_NAMESPACE)
+        .and_then(|namespace| namespace.downcast_mut::<HintNamespace>())
+        .ok_or_else(|| to_py_error(ExecScopeError::NoScopeError))
+}
+
+/// Formats the Python traceback of a failed hint, pointing the frames of the
+/// hint's code at their lines inside its `%{ %}` block.
+/// Returns None if the error wasn't raised by the hint's code.
+pub(crate) fn format_hint_traceback(
+    py: Python,
+    error: &PyErr,
+    hint: &HintDebugInfo,
+) -> PyResult<Option<String>> {
+    let traceback = py.import("traceback")?;
+    // The exception object itself doesn't hold the traceback fetched with it
+    let exception = traceback.getattr("TracebackException")?.call1((
+        error.get_type(py),
+        error.value(py),
+        error.traceback(py),
+    ))?;
+
+    let mut from_hint = false;
+    let mut frames = Vec::new();
+    for frame in exception.getattr("stack")?.iter()? {
+        let frame = frame?;
+        if frame.getattr("filename")?.extract::<&str>()? != HINT_FILENAME {
+            frames.push(frame);
+            continue;
+        }
+        from_hint = true;
+        let line: u32 = frame.getattr("lineno")?.extract()?;
+        let line = hint.location.start_line + hint.n_prefix_newlines + line.saturating_sub(1);
+        frames.push(traceback.getattr("FrameSummary")?.call1((
+            hint.location.input_file.filename.as_str(),
+            line,
+            frame.getattr("name")?,
+        ))?);
+    }
+    if !from_hint {
+        return Ok(None);
+    }
+
+    let stack = traceback
+        .getattr("StackSummary")?
+        .call_method1("from_list", (frames,))?;
+    exception.setattr("stack", stack)?;
+    let mut formatted = String::new();
+    for line in exception.call_method0("format")?.iter()? {
+        formatted.push_str(line?.extract()?);
+    }
+    Ok(Some(formatted))
+}
+
 pub(crate) fn add_scope_locals(
     globals: &PyDict,
     exec_scopes: &ExecutionScopes,
@@ -271,37 +702,111 @@ pub(crate) fn add_scope_locals(
     Ok(())
 }
 
-pub(crate) fn update_scope_hint_locals(
+/// Returns the Python variables of the current scope, as given to the functions
+/// registered for a hint.
+fn scope_variables<'py>(py: Python<'py>, exec_scopes: &ExecutionScopes) -> PyResult<&'py PyDict> {
+    let variables = PyDict::new(py);
+    add_scope_locals(variables, exec_scopes)?;
+    Ok(variables)
+}
+
+/// Writes the variables a registered function assigned or deleted in `scopes`
+/// back to the current scope.
+fn update_scope_variables(exec_scopes: &mut ExecutionScopes, scopes: &PyDict, py: Python) {
+    let deleted = exec_scopes
+        .get_local_variables()
+        .map(|variables| {
+            variables
+                .iter()
+                .filter(|(name, value)| {
+                    value.is::<PyObject>() && !scopes.contains(name.as_str()).unwrap_or(true)
+                })
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    for name in deleted {
+        exec_scopes.delete_variable(&name);
+    }
+
+    for (name, value) in scopes {
+        let name = name.to_string();
+        let unchanged = exec_scopes
+            .get_any_boxed_ref(&name)
+            .ok()
+            .and_then(|current| current.downcast_ref::<PyObject>())
+            .map_or(false, |current| current.is(value));
+        if !unchanged {
+            exec_scopes.assign_or_update_variable(&name, any_box!(value.to_object(py)));
+        }
+    }
+}
+
+/// Writes the names a hint assigned back to the hint locals or the current
+/// scope, and deletes the scope variables it deleted.
+fn update_scope_hint_locals(
     exec_scopes: &mut ExecutionScopes,
     hint_locals: &mut HashMap<String, PyObject>,
     static_locals: Option<&HashMap<String, PyObject>>,
+    names: &HintNames,
     globals: &PyDict,
     py: Python,
-) {
-    let static_local_names = static_locals
-        .map(|locals| locals.keys().collect::<Vec<&String>>())
-        .unwrap_or_default();
-    for (name, elem) in globals {
-        let name = name.to_string();
-        if !GLOBAL_NAMES.contains(&name.as_str()) && !static_local_names.contains(&&name) {
-            if hint_locals.keys().cloned().any(|x| x == name) {
-                hint_locals.insert(name, elem.to_object(py));
-            } else {
-                exec_scopes.assign_or_update_variable(&name, any_box!(elem.to_object(py)));
-            }
+) -> PyResult<()> {
+    let mut assigned = Vec::new();
+    for name in names.assigned.iter() {
+        if GLOBAL_NAMES.contains(&name.as_str())
+            || static_locals.map_or(false, |locals| locals.contains_key(name))
+        {
+            continue;
+        }
+        let elem = match globals.get_item(name) {
+            Some(elem) => elem,
+            None => continue,
+        };
+        if let Some(hint_local) = hint_locals.get_mut(name) {
+            *hint_local = elem.to_object(py);
+            continue;
+        }
+        exec_scopes.assign_or_update_variable(name, any_box!(elem.to_object(py)));
+        assigned.push(name);
+    }
+
+    let mut deleted = Vec::new();
+    for name in names.deleted.iter() {
+        let is_scope_variable = exec_scopes
+            .get_any_boxed_ref(name)
+            .map_or(false, |value| value.is::<PyObject>());
+        if is_scope_variable && !globals.contains(name)? {
+            exec_scopes.delete_variable(name);
+            deleted.push(name);
+        }
+    }
+
+    // Hints run with their own globals leave no namespace to keep in sync
+    let scope = exec_scopes.get_local_variables_mut().map_err(to_py_error)?;
+    if let Ok(namespace) = hint_namespace_mut(scope) {
+        namespace.scope_names.extend(assigned.into_iter().cloned());
+        for name in deleted {
+            namespace.scope_names.remove(name);
         }
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{biguint, relocatable::PyMaybeRelocatable, vm_core::PyVM};
+    use crate::{
+        biguint,
+        debug_info::{InputFile, SourceLocation},
+        relocatable::PyMaybeRelocatable,
+        vm_core::PyVM,
+    };
     use cairo_vm::{
         felt::Felt252,
         hint_processor::{
             builtin_hint_processor::builtin_hint_processor_definition::{
-                BuiltinHintProcessor, HintProcessorData,
+                BuiltinHintProcessor, HintFunc, HintProcessorData,
             },
             hint_processor_definition::HintReference,
         },
@@ -310,7 +815,7 @@ mod test {
             relocatable::{MaybeRelocatable, Relocatable},
         },
     };
-    use pyo3::{PyObject, Python, ToPyObject};
+    use pyo3::{exceptions::PyValueError, PyObject, Python, ToPyObject};
     use std::{any::Any, collections::HashMap, rc::Rc};
 
     #[test]
@@ -503,6 +1008,391 @@ mod test {
             .is_ok());
     }
 
+    #[test]
+    fn hint_code_is_compiled_once() {
+        let mut vm = PyVM::new(false);
+        vm.vm.borrow_mut().set_pc(Relocatable::from((0, 3)));
+
+        let hint_data =
+            HintProcessorData::new_default("calls.append(ap)".to_string(), HashMap::new());
+        let hint_data_dictionary = HashMap::from([(3, vec![any_box!(hint_data)])]);
+
+        Python::with_gil(|py| {
+            let calls = pyo3::types::PyList::empty(py);
+            let static_locals = HashMap::from([("calls".to_string(), calls.to_object(py))]);
+            let step_hint = |vm: &mut PyVM| {
+                vm.step_hint(
+                    &mut BuiltinHintProcessor::new_empty(),
+                    &mut HashMap::new(),
+                    &mut ExecutionScopes::new(),
+                    &hint_data_dictionary,
+                    Rc::new(HashMap::new()),
+                    &HashMap::new(),
+                    Some(&static_locals),
+                )
+                .unwrap()
+            };
+
+            step_hint(&mut vm);
+            let code = vm.compiled_hints[&(3, 0)].code.clone_ref(py);
+            step_hint(&mut vm);
+
+            assert_eq!(calls.len(), 2);
+            assert_eq!(vm.compiled_hints.len(), 1);
+            assert!(vm.compiled_hints[&(3, 0)].code.is(&code));
+        });
+    }
+
+    #[test]
+    fn hint_function_runs_without_hint_globals() {
+        let mut vm = PyVM::new(false);
+        vm.vm.borrow_mut().set_pc(Relocatable::from((0, 3)));
+        let hint_data = HintProcessorData::new_default("pass".to_string(), HashMap::new());
+        let hint_data_dictionary = HashMap::from([(3, vec![any_box!(hint_data)])]);
+        let mut exec_scopes = ExecutionScopes::new();
+
+        Python::with_gil(|py| {
+            let function = py
+                .eval(
+                    "lambda vm, ids, memory, scopes: scopes.update(pc=vm.run_context.pc.offset)",
+                    None,
+                    None,
+                )
+                .unwrap();
+            vm.hint_functions
+                .insert("pass".to_string(), function.to_object(py));
+            vm.step_hint(
+                &mut BuiltinHintProcessor::new_empty(),
+                &mut HashMap::new(),
+                &mut exec_scopes,
+                &hint_data_dictionary,
+                Rc::new(HashMap::new()),
+                &HashMap::new(),
+                None,
+            )
+            .unwrap();
+
+            assert!(vm.compiled_hints.is_empty());
+            assert!(hint_globals(&exec_scopes.data[0]).is_none());
+            let pc = exec_scopes.get_any_boxed_ref("pc").unwrap();
+            let pc: usize = pc.downcast_ref::<PyObject>().unwrap().extract(py).unwrap();
+            assert_eq!(pc, 3);
+        });
+    }
+
+    #[test]
+    fn hint_names_of_code() {
+        let code = "x = 1\ndel y\nz.append(2)\nfor i in range(2): pass\n\
+                    def f():\n    global w\n    w = [v for v in range(3)]\n    del u";
+        Python::with_gil(|py| {
+            let names = &CompiledHint::new(py, code).unwrap().names;
+            let sorted = |names: &HashSet<String>| {
+                let mut names = names.iter().cloned().collect::<Vec<_>>();
+                names.sort();
+                names
+            };
+            assert_eq!(sorted(&names.assigned), ["f", "i", "w", "x"]);
+            assert_eq!(sorted(&names.deleted), ["y"]);
+        });
+    }
+
+    /// Runs the hint at every pc of `hints` in order, with `hint_processor`
+    /// running the Rust ones.
+    fn step_hints(
+        vm: &mut PyVM,
+        hint_processor: &mut BuiltinHintProcessor,
+        exec_scopes: &mut ExecutionScopes,
+        hints: &[&str],
+        mut after_hint: impl FnMut(usize, &ExecutionScopes),
+    ) {
+        let hint_data_dictionary: HashMap<usize, Vec<Box<dyn Any>>> = hints
+            .iter()
+            .enumerate()
+            .map(|(pc, code)| {
+                let hint_data = HintProcessorData::new_default(code.to_string(), HashMap::new());
+                (pc, vec![any_box!(hint_data)])
+            })
+            .collect();
+        for pc in 0..hints.len() {
+            vm.vm.borrow_mut().set_pc(Relocatable::from((0, pc)));
+            vm.step_hint(
+                hint_processor,
+                &mut HashMap::new(),
+                exec_scopes,
+                &hint_data_dictionary,
+                Rc::new(HashMap::new()),
+                &HashMap::new(),
+                None,
+            )
+            .unwrap();
+            after_hint(pc, exec_scopes);
+        }
+    }
+
+    fn hint_globals(scope: &HashMap<String, Box<dyn Any>>) -> Option<&Py<PyDict>> {
+        scope
+            .get(HINT_NAMESPACE)
+            .and_then(|namespace| namespace.downcast_ref::<HintNamespace>())
+            .map(|namespace| &namespace.globals)
+    }
+
+    #[test]
+    fn hint_globals_are_kept_per_scope() {
+        let mut vm = PyVM::new(false);
+        let hints = [
+            "x = 1\nvm_enter_scope({'y': 2})",
+            "assert 'x' not in globals() and y == 2\nvm_exit_scope()",
+            "assert x == 1 and 'y' not in globals()",
+        ];
+        let mut exec_scopes = ExecutionScopes::new();
+
+        let mut namespaces = Vec::new();
+        step_hints(
+            &mut vm,
+            &mut BuiltinHintProcessor::new_empty(),
+            &mut exec_scopes,
+            &hints,
+            |_, exec_scopes| namespaces.push(hint_globals(&exec_scopes.data[0]).cloned()),
+        );
+
+        // The inner scope's globals are dropped when it's exited
+        assert_eq!(exec_scopes.data.len(), 1);
+        assert!(namespaces[0]
+            .as_ref()
+            .unwrap()
+            .is(namespaces[2].as_ref().unwrap()));
+        Python::with_gil(|py| {
+            let x = exec_scopes.get_any_boxed_ref("x").unwrap();
+            let x: u32 = x.downcast_ref::<PyObject>().unwrap().extract(py).unwrap();
+            assert_eq!(x, 1);
+        });
+    }
+
+    #[test]
+    fn hint_globals_follow_scopes_changed_by_rust_hints() {
+        let mut vm = PyVM::new(false);
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let rust_hints = [
+            (
+                "rust_enter_scope",
+                HintFunc(Box::new(|_, exec_scopes, _, _, _| {
+                    exec_scopes.enter_scope(HashMap::new());
+                    Ok(())
+                })),
+            ),
+            (
+                "rust_exit_scope",
+                HintFunc(Box::new(|_, exec_scopes, _, _, _| {
+                    exec_scopes.exit_scope().map_err(HintError::FromScopeError)
+                })),
+            ),
+            (
+                "rust_store_x",
+                HintFunc(Box::new(|_, exec_scopes, _, _, _| {
+                    exec_scopes.insert_value("x", Felt252::new(5));
+                    Ok(())
+                })),
+            ),
+            (
+                "rust_delete_z",
+                HintFunc(Box::new(|_, exec_scopes, _, _, _| {
+                    exec_scopes.delete_variable("z");
+                    Ok(())
+                })),
+            ),
+        ];
+        for (code, hint_func) in rust_hints {
+            hint_processor.add_hint(code.to_string(), Rc::new(hint_func));
+        }
+        let hints = [
+            "x = 1",
+            "rust_enter_scope",
+            "assert 'x' not in globals()\ny = 2",
+            // A new scope at the same depth doesn't see the old one's variables
+            "rust_exit_scope",
+            "rust_enter_scope",
+            "assert 'x' not in globals() and 'y' not in globals()",
+            "rust_exit_scope",
+            "assert x == 1 and 'y' not in globals()\nz = 3",
+            // Variables replaced by Rust values or deleted are neither seen nor
+            // written back by later Python hints
+            "rust_store_x",
+            "rust_delete_z",
+            "assert 'x' not in globals() and 'z' not in globals()\nw = 4",
+        ];
+        let mut exec_scopes = ExecutionScopes::new();
+
+        step_hints(
+            &mut vm,
+            &mut hint_processor,
+            &mut exec_scopes,
+            &hints,
+            |_, _| {},
+        );
+
+        assert_eq!(exec_scopes.data.len(), 1);
+        assert_eq!(exec_scopes.get::<Felt252>("x").unwrap(), Felt252::new(5));
+        assert!(exec_scopes.get_any_boxed_ref("z").is_err());
+        assert!(exec_scopes.get_any_boxed_ref("w").is_ok());
+    }
+
+    #[test]
+    fn step_hooks_receive_run_context_and_instruction() {
+        let mut vm = PyVM::new(false);
+
+        for _ in 0..2 {
+            vm.vm.borrow_mut().add_memory_segment();
+        }
+
+        vm.vm.borrow_mut().set_pc(Relocatable::from((0, 0)));
+        vm.vm.borrow_mut().set_ap(1);
+        vm.vm.borrow_mut().set_fp(1);
+        // The instruction reads [fp - 1] as op0, which it doesn't use
+        vm.vm
+            .borrow_mut()
+            .insert_value(Relocatable::from((1, 0)), 0)
+            .unwrap();
+
+        // [ap] = 123; ap++
+        vm.vm
+            .borrow_mut()
+            .insert_value(
+                Relocatable::from((0, 0)),
+                Felt252::new(0x480680017fff8000_u64),
+            )
+            .unwrap();
+        vm.vm
+            .borrow_mut()
+            .insert_value(Relocatable::from((0, 1)), 123)
+            .unwrap();
+
+        Python::with_gil(|py| {
+            let calls = pyo3::types::PyList::empty(py);
+            let globals = PyDict::new(py);
+            globals.set_item("calls", calls).unwrap();
+            let pre_hook = py
+                .eval(
+                    "lambda ctx, inst: calls.append(('pre', ctx.pc.offset, ctx.ap.offset, inst.opcode))",
+                    Some(globals),
+                    None,
+                )
+                .unwrap();
+            let post_hook = py
+                .eval(
+                    "lambda ctx, inst: calls.append(('post', ctx.pc.offset, ctx.ap.offset, inst.opcode))",
+                    Some(globals),
+                    None,
+                )
+                .unwrap();
+            vm.step_hooks.pre_step.push(pre_hook.to_object(py));
+            vm.step_hooks.post_step.push(post_hook.to_object(py));
+
+            assert!(vm
+                .step(
+                    &mut BuiltinHintProcessor::new_empty(),
+                    &mut HashMap::new(),
+                    &mut ExecutionScopes::new(),
+                    &HashMap::new(),
+                    Rc::new(HashMap::new()),
+                    &HashMap::new(),
+                    None,
+                )
+                .is_ok());
+
+            assert_eq!(
+                calls
+                    .extract::<Vec<(String, usize, usize, String)>>()
+                    .unwrap(),
+                vec![
+                    ("pre".to_string(), 0, 1, "ASSERT_EQ".to_string()),
+                    ("post".to_string(), 2, 2, "ASSERT_EQ".to_string()),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn failing_step_hook_stops_execution() {
+        let mut vm = PyVM::new(false);
+
+        for _ in 0..2 {
+            vm.vm.borrow_mut().add_memory_segment();
+        }
+
+        vm.vm.borrow_mut().set_pc(Relocatable::from((0, 0)));
+        vm.vm.borrow_mut().set_ap(2);
+        vm.vm.borrow_mut().set_fp(2);
+
+        vm.vm
+            .borrow_mut()
+            .insert_value(Relocatable::from((0, 0)), 2345108766317314046)
+            .unwrap();
+
+        Python::with_gil(|py| {
+            let hook = py.eval("lambda ctx, inst: 1 / 0", None, None).unwrap();
+            vm.step_hooks.pre_step.push(hook.to_object(py));
+        });
+
+        assert!(vm
+            .step(
+                &mut BuiltinHintProcessor::new_empty(),
+                &mut HashMap::new(),
+                &mut ExecutionScopes::new(),
+                &HashMap::new(),
+                Rc::new(HashMap::new()),
+                &HashMap::new(),
+                None,
+            )
+            .is_err());
+        assert_eq!(vm.vm.borrow().get_pc(), Relocatable::from((0, 0)));
+    }
+
+    #[test]
+    fn failed_steps_are_not_covered() {
+        let mut vm = PyVM::new(false);
+        vm.coverage = Some(CoverageTracker::default());
+        for _ in 0..4 {
+            vm.vm.borrow_mut().add_memory_segment();
+        }
+        vm.vm.borrow_mut().set_pc(Relocatable::from((0, 0)));
+        vm.vm.borrow_mut().set_ap(2);
+        vm.vm.borrow_mut().set_fp(2);
+
+        let step = |vm: &mut PyVM| {
+            vm.step(
+                &mut BuiltinHintProcessor::new_empty(),
+                &mut HashMap::new(),
+                &mut ExecutionScopes::new(),
+                &HashMap::new(),
+                Rc::new(HashMap::new()),
+                &HashMap::new(),
+                None,
+            )
+        };
+
+        // There's no instruction at pc yet
+        assert!(step(&mut vm).is_err());
+        assert_eq!(vm.coverage, Some(CoverageTracker::default()));
+
+        vm.vm
+            .borrow_mut()
+            .insert_value(Relocatable::from((0, 0)), 2345108766317314046)
+            .unwrap();
+        vm.vm
+            .borrow_mut()
+            .insert_value(Relocatable::from((1, 0)), Relocatable::from((2, 0)))
+            .unwrap();
+        vm.vm
+            .borrow_mut()
+            .insert_value(Relocatable::from((1, 1)), Relocatable::from((3, 0)))
+            .unwrap();
+        assert!(step(&mut vm).is_ok());
+
+        let mut expected = CoverageTracker::default();
+        expected.record_pc(0);
+        assert_eq!(vm.coverage, Some(expected));
+    }
+
     #[test]
     fn scopes_hint() {
         let mut vm = PyVM::new(false);
@@ -1081,4 +1971,45 @@ lista_b = [lista_a[k] for k in range(2)]";
         assert_eq!(run_context.ap(), (1, 0).into());
         assert_eq!(run_context.fp(), (1, 0).into());
     }
+
+    #[test]
+    fn format_failed_hint_traceback() {
+        let mut vm = PyVM::new(false);
+        let code = "def fail():\n    raise ValueError('hint failed')\nfail()";
+        let hint_data = HintProcessorData::new_default(code.to_string(), HashMap::new());
+        let error = vm
+            .execute_hint(
+                &hint_data,
+                &mut HashMap::new(),
+                &mut ExecutionScopes::new(),
+                &HashMap::new(),
+                Rc::new(HashMap::new()),
+                None,
+            )
+            .unwrap_err();
+
+        let hint = HintDebugInfo {
+            location: SourceLocation {
+                input_file: InputFile {
+                    filename: "program.cairo".to_string(),
+                },
+                start_line: 10,
+                end_line: 14,
+            },
+            n_prefix_newlines: 1,
+        };
+        Python::with_gil(|py| {
+            let traceback = format_hint_traceback(py, &error, &hint).unwrap().unwrap();
+            assert!(traceback.contains("File \"program.cairo\", line 13, in <module>"));
+            assert!(traceback.contains("File \"program.cairo\", line 12, in fail"));
+            assert!(traceback.ends_with("ValueError: hint failed\n"));
+            assert!(!traceback.contains(HINT_FILENAME));
+
+            let other_error = PyValueError::new_err("not from a hint");
+            assert_eq!(
+                format_hint_traceback(py, &other_error, &hint).unwrap(),
+                None
+            );
+        });
+    }
 }
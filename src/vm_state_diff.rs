@@ -0,0 +1,223 @@
+use crate::encoded_files::{MemoryCell, MemoryReader, TraceEntry, TraceReader};
+use num_bigint::BigUint;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+};
+
+/// The first step at which two traces differ. An entry is missing when its
+/// trace ended before the other one.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TraceDiff {
+    pub step: usize,
+    pub expected: Option<TraceEntry>,
+    pub actual: Option<TraceEntry>,
+}
+
+/// An address holding different values in two memories, or only in one of them.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MemoryDiff {
+    pub address: usize,
+    pub expected: Option<BigUint>,
+    pub actual: Option<BigUint>,
+}
+
+impl fmt::Display for TraceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format_entry = |entry: &Option<TraceEntry>| match entry {
+            Some((pc, ap, fp)) => format!("(pc={pc}, ap={ap}, fp={fp})"),
+            None => "end of trace".to_string(),
+        };
+        write!(
+            f,
+            "Traces differ at step {}: expected {}, found {}",
+            self.step,
+            format_entry(&self.expected),
+            format_entry(&self.actual)
+        )
+    }
+}
+
+impl fmt::Display for MemoryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format_value = |value: &Option<BigUint>| match value {
+            Some(value) => value.to_string(),
+            None => "no value".to_string(),
+        };
+        write!(
+            f,
+            "Address {}: expected {}, found {}",
+            self.address,
+            format_value(&self.expected),
+            format_value(&self.actual)
+        )
+    }
+}
+
+/// Walks both traces in lockstep, stopping at the first differing step.
+pub(crate) fn diff_traces(
+    expected: impl Iterator<Item = io::Result<TraceEntry>>,
+    actual: impl Iterator<Item = io::Result<TraceEntry>>,
+) -> io::Result<Option<TraceDiff>> {
+    let (mut expected, mut actual) = (expected.fuse(), actual.fuse());
+    let mut step = 0;
+    loop {
+        let (expected, actual) = match (expected.next(), actual.next()) {
+            (None, None) => return Ok(None),
+            (expected, actual) => (expected.transpose()?, actual.transpose()?),
+        };
+        if expected != actual {
+            return Ok(Some(TraceDiff {
+                step,
+                expected,
+                actual,
+            }));
+        }
+        step += 1;
+    }
+}
+
+/// Returns every differing address, sorted. The cells may come in any order,
+/// as cairo-lang doesn't sort them by address.
+pub(crate) fn diff_memory(
+    expected: impl Iterator<Item = io::Result<MemoryCell>>,
+    actual: impl Iterator<Item = io::Result<MemoryCell>>,
+) -> io::Result<Vec<MemoryDiff>> {
+    let mut expected = collect_memory(expected)?;
+    let mut actual = collect_memory(actual)?;
+
+    let mut addresses = expected
+        .keys()
+        .chain(actual.keys())
+        .copied()
+        .collect::<Vec<_>>();
+    addresses.sort_unstable();
+    addresses.dedup();
+    Ok(addresses
+        .into_iter()
+        .filter_map(|address| {
+            let (expected, actual) = (expected.remove(&address), actual.remove(&address));
+            (expected != actual).then_some(MemoryDiff {
+                address,
+                expected,
+                actual,
+            })
+        })
+        .collect())
+}
+
+fn collect_memory(
+    cells: impl Iterator<Item = io::Result<MemoryCell>>,
+) -> io::Result<BTreeMap<usize, BigUint>> {
+    let mut memory = BTreeMap::new();
+    for cell in cells {
+        let (address, value) = cell?;
+        if memory.insert(address, value).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Address {address} has two values"),
+            ));
+        }
+    }
+    Ok(memory)
+}
+
+/// Compares two trace files written by either cairo-lang or cairo-vm.
+pub fn diff_trace_files(expected: &Path, actual: &Path) -> io::Result<Option<TraceDiff>> {
+    diff_traces(
+        TraceReader::new(BufReader::new(File::open(expected)?)),
+        TraceReader::new(BufReader::new(File::open(actual)?)),
+    )
+}
+
+/// Compares two memory files written by either cairo-lang or cairo-vm.
+pub fn diff_memory_files(expected: &Path, actual: &Path) -> io::Result<Vec<MemoryDiff>> {
+    diff_memory(
+        MemoryReader::new(BufReader::new(File::open(expected)?)),
+        MemoryReader::new(BufReader::new(File::open(actual)?)),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entries<T>(entries: Vec<T>) -> impl Iterator<Item = io::Result<T>> {
+        entries.into_iter().map(Ok)
+    }
+
+    #[test]
+    fn first_divergent_trace_step() {
+        let expected = vec![(1, 10, 10), (3, 11, 10), (5, 12, 10)];
+        assert_eq!(
+            diff_traces(entries(expected.clone()), entries(expected.clone())).unwrap(),
+            None
+        );
+
+        let actual = vec![(1, 10, 10), (4, 11, 10), (6, 12, 10)];
+        let diff = diff_traces(entries(expected.clone()), entries(actual)).unwrap();
+        assert_eq!(
+            diff,
+            Some(TraceDiff {
+                step: 1,
+                expected: Some((3, 11, 10)),
+                actual: Some((4, 11, 10)),
+            })
+        );
+        assert_eq!(
+            diff.unwrap().to_string(),
+            "Traces differ at step 1: expected (pc=3, ap=11, fp=10), found (pc=4, ap=11, fp=10)"
+        );
+
+        let diff = diff_traces(entries(expected.clone()), entries(expected[..2].to_vec()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.step, 2);
+        assert_eq!(diff.actual, None);
+        assert!(diff.to_string().ends_with("found end of trace"));
+    }
+
+    #[test]
+    fn differing_memory_addresses() {
+        let value = |value: u32| BigUint::from(value);
+        let expected = vec![(3, value(7)), (1, value(5)), (2, value(6))];
+        let actual = vec![(1, value(5)), (2, value(8)), (4, value(9))];
+
+        let diffs = diff_memory(entries(expected), entries(actual)).unwrap();
+        assert_eq!(
+            diffs,
+            vec![
+                MemoryDiff {
+                    address: 2,
+                    expected: Some(value(6)),
+                    actual: Some(value(8)),
+                },
+                MemoryDiff {
+                    address: 3,
+                    expected: Some(value(7)),
+                    actual: None,
+                },
+                MemoryDiff {
+                    address: 4,
+                    expected: None,
+                    actual: Some(value(9)),
+                },
+            ]
+        );
+        assert_eq!(diffs[0].to_string(), "Address 2: expected 6, found 8");
+        assert_eq!(
+            diffs[1].to_string(),
+            "Address 3: expected 7, found no value"
+        );
+    }
+
+    #[test]
+    fn memory_with_repeated_address() {
+        let memory = vec![(1, BigUint::from(1_u32)), (1, BigUint::from(2_u32))];
+        let err = diff_memory(entries(memory), entries(vec![])).unwrap_err();
+        assert_eq!(err.to_string(), "Address 1 has two values");
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{
+    encoded_files::{MemoryCell, MemoryReader, TraceEntry, TraceReader},
+    vm_state_diff::{diff_memory_files, diff_trace_files},
+};
+use num_bigint::BigUint;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::PathBuf,
+};
+
+/// Malformed contents are reported as ValueError, while failing to read the
+/// file is left as an OSError.
+pub(crate) fn to_py_io_error(err: io::Error) -> PyErr {
+    if err.kind() == io::ErrorKind::InvalidData {
+        PyValueError::new_err(err.to_string())
+    } else {
+        err.into()
+    }
+}
+
+/// Reads a trace file written by `cairo_run` as a list of (pc, ap, fp) tuples.
+#[pyfunction]
+pub fn read_trace_file(path: PathBuf) -> PyResult<Vec<TraceEntry>> {
+    TraceReader::new(BufReader::new(File::open(path)?))
+        .collect::<io::Result<_>>()
+        .map_err(to_py_io_error)
+}
+
+/// Reads a memory file written by `cairo_run` as a list of (address, value) tuples.
+#[pyfunction]
+pub fn read_memory_file(path: PathBuf) -> PyResult<Vec<MemoryCell>> {
+    MemoryReader::new(BufReader::new(File::open(path)?))
+        .collect::<io::Result<_>>()
+        .map_err(to_py_io_error)
+}
+
+/// Iterates over the entries of a trace file without loading it whole.
+#[pyfunction]
+pub fn iter_trace_file(path: PathBuf) -> PyResult<PyTraceFileIterator> {
+    let reader = TraceReader::new(BufReader::new(File::open(path)?));
+    Ok(PyTraceFileIterator(reader))
+}
+
+/// Iterates over the cells of a memory file without loading it whole.
+#[pyfunction]
+pub fn iter_memory_file(path: PathBuf) -> PyResult<PyMemoryFileIterator> {
+    let reader = MemoryReader::new(BufReader::new(File::open(path)?));
+    Ok(PyMemoryFileIterator(reader))
+}
+
+#[pyclass(name = "TraceFileIterator")]
+pub struct PyTraceFileIterator(TraceReader<BufReader<File>>);
+
+#[pymethods]
+impl PyTraceFileIterator {
+    pub fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    pub fn __next__(mut slf: PyRefMut<Self>) -> PyResult<Option<TraceEntry>> {
+        slf.0.next().transpose().map_err(to_py_io_error)
+    }
+}
+
+#[pyclass(name = "MemoryFileIterator")]
+pub struct PyMemoryFileIterator(MemoryReader<BufReader<File>>);
+
+#[pymethods]
+impl PyMemoryFileIterator {
+    pub fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    pub fn __next__(mut slf: PyRefMut<Self>) -> PyResult<Option<MemoryCell>> {
+        slf.0.next().transpose().map_err(to_py_io_error)
+    }
+}
+
+/// Returns the first divergent step of two trace files as
+/// (step, expected (pc, ap, fp), actual (pc, ap, fp)), or None if they match.
+/// The entry of a trace that ended early is None.
+#[pyfunction]
+pub fn compare_trace_files(
+    expected: PathBuf,
+    actual: PathBuf,
+) -> PyResult<Option<(usize, Option<TraceEntry>, Option<TraceEntry>)>> {
+    let diff = diff_trace_files(&expected, &actual).map_err(to_py_io_error)?;
+    Ok(diff.map(|diff| (diff.step, diff.expected, diff.actual)))
+}
+
+/// Returns the differing addresses of two memory files as
+/// (address, expected value, actual value) tuples, with None for a missing value.
+#[pyfunction]
+pub fn compare_memory_files(
+    expected: PathBuf,
+    actual: PathBuf,
+) -> PyResult<Vec<(usize, Option<BigUint>, Option<BigUint>)>> {
+    let diffs = diff_memory_files(&expected, &actual).map_err(to_py_io_error)?;
+    Ok(diffs
+        .into_iter()
+        .map(|diff| (diff.address, diff.expected, diff.actual))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cairo_runner::PyCairoRunner;
+    use std::{collections::HashMap, env::temp_dir, fs};
+
+    #[test]
+    fn read_trace_and_memory_files() {
+        let program = fs::read_to_string("cairo_programs/fibonacci.json").unwrap();
+        let mut runner =
+            PyCairoRunner::new(program, Some("main".to_string()), None, false).unwrap();
+        let trace_path = temp_dir().join("fibonacci_decode.trace");
+        let memory_path = temp_dir().join("fibonacci_decode.memory");
+        runner
+            .cairo_run_py(
+                false,
+                trace_path.to_str(),
+                memory_path.to_str(),
+                None,
+                None,
+                None,
+                false,
+                false,
+            )
+            .unwrap();
+
+        let trace = runner.get_relocated_trace().unwrap();
+        assert_eq!(read_trace_file(trace_path.clone()).unwrap(), trace);
+        let streamed = iter_trace_file(trace_path.clone()).unwrap().0;
+        assert_eq!(streamed.collect::<io::Result<Vec<_>>>().unwrap(), trace);
+
+        let memory = read_memory_file(memory_path.clone()).unwrap();
+        assert!(memory.windows(2).all(|cells| cells[0].0 < cells[1].0));
+        assert_eq!(
+            memory.into_iter().collect::<HashMap<_, _>>(),
+            runner.get_relocated_memory().unwrap()
+        );
+
+        _ = fs::remove_file(trace_path);
+        _ = fs::remove_file(memory_path);
+    }
+
+    #[test]
+    fn compare_files_of_different_runs() {
+        let run = |program: &str| {
+            let json = fs::read_to_string(format!("cairo_programs/{program}.json")).unwrap();
+            let mut runner =
+                PyCairoRunner::new(json, Some("main".to_string()), None, false).unwrap();
+            let trace_path = temp_dir().join(format!("{program}_compare.trace"));
+            let memory_path = temp_dir().join(format!("{program}_compare.memory"));
+            runner
+                .cairo_run_py(
+                    false,
+                    trace_path.to_str(),
+                    memory_path.to_str(),
+                    None,
+                    None,
+                    None,
+                    false,
+                    false,
+                )
+                .unwrap();
+            (trace_path, memory_path)
+        };
+        let (fibonacci_trace, fibonacci_memory) = run("fibonacci");
+        let (not_main_trace, not_main_memory) = run("not_main");
+
+        assert_eq!(
+            compare_trace_files(fibonacci_trace.clone(), fibonacci_trace.clone()).unwrap(),
+            None
+        );
+        assert!(
+            compare_memory_files(fibonacci_memory.clone(), fibonacci_memory.clone())
+                .unwrap()
+                .is_empty()
+        );
+
+        let (_, expected, actual) =
+            compare_trace_files(fibonacci_trace.clone(), not_main_trace.clone())
+                .unwrap()
+                .unwrap();
+        assert_ne!(expected, actual);
+        assert!(
+            !compare_memory_files(fibonacci_memory.clone(), not_main_memory.clone())
+                .unwrap()
+                .is_empty()
+        );
+
+        for path in [
+            fibonacci_trace,
+            fibonacci_memory,
+            not_main_trace,
+            not_main_memory,
+        ] {
+            _ = fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn read_nonexistent_file() {
+        let err = read_trace_file(PathBuf::from("cairo_programs/missing.trace")).unwrap_err();
+        Python::with_gil(|py| {
+            assert!(err.is_instance_of::<pyo3::exceptions::PyOSError>(py));
+        });
+    }
+}
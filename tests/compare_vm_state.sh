@@ -1,6 +1,7 @@
 #!/usr/bin/env sh
 
 tests_path="../cairo_programs"
+compare_vm_state="../target/release/compare_vm_state"
 exit_code=0
 trace=false
 memory=false
@@ -24,7 +25,7 @@ for file in $(ls $tests_path | grep .cairo$ | sed -E 's/\.cairo$//'); do
     echo "$file"
     if ! ([ "$file" = "blake2s_felt" ] || [ "$file" = "blake2s_finalize" ] || [ "$file" = "blake2s_integration_tests" ] || [ "$file" = "blake2s_hello_world_hash" ] || [ "$file" = "dict_squash" ] || [ "$file" = "squash_dict" ] || [ "$file" = "dict_write" ] || [ "$file" = "dict_write" ] || [ "$file" = "dict_update" ] || [ "$file" = "dict_read" ]); then
         if $trace; then
-            if ! diff -q $path_file.trace $path_file.rs.trace; then
+            if ! $compare_vm_state trace $path_file.trace $path_file.rs.trace; then
                 echo "Traces for $file differ"
                 exit_code=1
                 failed_tests=$((failed_tests + 1))
@@ -34,7 +35,7 @@ for file in $(ls $tests_path | grep .cairo$ | sed -E 's/\.cairo$//'); do
         fi
 
         if $memory; then
-            if ! ./memory_comparator.py $path_file.memory $path_file.rs.memory; then
+            if ! $compare_vm_state memory $path_file.memory $path_file.rs.memory; then
                 echo "Memory differs for $file"
                 exit_code=1
                 failed_tests=$((failed_tests + 1))